@@ -0,0 +1,201 @@
+use crate::error::NetworkError;
+use crate::simulation::listener::flood_analyzer::{FloodReport, StormWindow};
+use crate::simulation::listener::journey_tracker::{JourneyKey, PacketJourney};
+use crate::simulation::listener::sequence_diagram::{self, DiagramFormat};
+use crate::simulation::listener::session_tracker::{SessionFilter, SessionSummary};
+use crate::simulation::metrics::PacketTypeLabel;
+use crate::simulation::sniffer::pcapng;
+use crate::simulation::state::SimulationState;
+use crate::simulation::topology::reliability::{self, PdrSource, RouteReliability};
+use crate::simulation::topology::routing;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use tauri::State;
+use wg_2024::network::NodeId;
+
+/// Returns the analytics of the flood discoveries, most recent first.
+///
+/// # Arguments
+///
+/// * `initiator` - Only return the floods started by this node, if set.
+#[tauri::command]
+pub fn get_flood_analysis(
+    state: State<Arc<Mutex<SimulationState>>>,
+    initiator: Option<NodeId>,
+) -> FloodReport {
+    let state = state.lock();
+    let network_size = state.get_graph().get_nodes().len();
+    let storm_window = StormWindow::for_discovery_interval(state.get_discovery_interval());
+    state.get_flood_analyzer().report(
+        initiator,
+        network_size,
+        storm_window,
+        state.get_clock().now_ms(),
+    )
+}
+
+/// Returns the delivery state of the sessions matching `filter`, most recent first.
+#[tauri::command]
+pub fn get_sessions(
+    state: State<Arc<Mutex<SimulationState>>>,
+    filter: Option<SessionFilter>,
+) -> Vec<SessionSummary> {
+    let state = state.lock();
+    state
+        .get_session_tracker()
+        .sessions(&filter.unwrap_or_default(), state.get_clock().now_ms())
+}
+
+/// Returns the hop-by-hop journey of a packet.
+///
+/// # Arguments
+///
+/// * `source` - The node that owns the session (the sender of the fragments).
+/// * `session_id` - The session of the packet.
+/// * `packet_type` - The type of the packet.
+/// * `index` - The fragment index, or the flood id for flood responses.
+#[tauri::command]
+pub fn get_packet_journey(
+    state: State<Arc<Mutex<SimulationState>>>,
+    source: NodeId,
+    session_id: u64,
+    packet_type: PacketTypeLabel,
+    index: u64,
+) -> Result<PacketJourney, NetworkError> {
+    let key = JourneyKey {
+        source,
+        session_id,
+        packet_type,
+        index,
+    };
+    state
+        .lock()
+        .get_journey_tracker()
+        .journey(&key)
+        .cloned()
+        .ok_or_else(|| {
+            NetworkError::PacketNotFound(format!(
+                "{:?} {} of session {} from {}",
+                packet_type, index, session_id, source
+            ))
+        })
+}
+
+/// Exports the journeys of the session `(source, session_id)` as a sequence diagram.
+///
+/// # Arguments
+///
+/// * `source` - The node that owns the session (the sender of the fragments).
+/// * `session_id` - The session to export.
+/// * `format` - Mermaid (default) or PlantUML.
+#[tauri::command]
+pub fn export_sequence_diagram(
+    state: State<Arc<Mutex<SimulationState>>>,
+    source: NodeId,
+    session_id: u64,
+    format: Option<DiagramFormat>,
+) -> Result<String, NetworkError> {
+    let state = state.lock();
+    let journeys = state
+        .get_journey_tracker()
+        .session_journeys(source, session_id);
+    if journeys.is_empty() {
+        return Err(NetworkError::PacketNotFound(format!(
+            "session {} from {}",
+            session_id, source
+        )));
+    }
+    Ok(sequence_diagram::render(
+        format.unwrap_or_default(),
+        (source, session_id),
+        &journeys,
+        state.get_graph(),
+    ))
+}
+
+/// Writes the packets captured by the sniffer during a run to `path`, in pcapng format.
+/// Returns the number of packets written.
+///
+/// # Arguments
+///
+/// * `run_id` - The run to export, the current (or last) run if not set.
+#[tauri::command]
+pub fn export_pcapng(
+    state: State<Arc<Mutex<SimulationState>>>,
+    path: String,
+    run_id: Option<u64>,
+) -> Result<usize, NetworkError> {
+    // The file is written without holding the lock
+    let (capture, count) = {
+        let state = state.lock();
+        let run_id = run_id.unwrap_or(state.get_run_id());
+        let packets: Vec<_> = state.get_sniffer().captured(run_id).collect();
+        if packets.is_empty() {
+            return Err(NetworkError::PacketNotFound(format!("run {}", run_id)));
+        }
+        (
+            pcapng::write_capture(packets.iter().copied()),
+            packets.len(),
+        )
+    };
+
+    std::fs::write(&path, capture).map_err(|e| NetworkError::PathError(e.to_string()))?;
+    Ok(count)
+}
+
+/// Estimates the delivery probability of a route, with the configured and the measured PDRs.
+///
+/// # Arguments
+///
+/// * `hops` - The route, from the source host to the destination host.
+/// * `fragments` - Number of fragments of the message. Defaults to 1.
+#[tauri::command]
+pub fn get_route_reliability(
+    state: State<Arc<Mutex<SimulationState>>>,
+    hops: Vec<NodeId>,
+    fragments: Option<u64>,
+) -> Result<RouteReliability, NetworkError> {
+    let state = state.lock();
+    reliability::route_reliability(
+        &state.get_topology(),
+        state.get_metrics(),
+        hops,
+        fragments.unwrap_or(1),
+    )
+}
+
+/// Returns the route between two hosts with the highest probability of delivering a
+/// fragment, with its reliability.
+///
+/// # Arguments
+///
+/// * `pdr_source` - The PDRs the route is chosen with. Defaults to the configured ones.
+/// * `fragments` - Number of fragments of the message. Defaults to 1.
+#[tauri::command]
+pub fn get_most_reliable_route(
+    state: State<Arc<Mutex<SimulationState>>>,
+    source: NodeId,
+    destination: NodeId,
+    pdr_source: Option<PdrSource>,
+    fragments: Option<u64>,
+) -> Result<RouteReliability, NetworkError> {
+    let state = state.lock();
+    let graph = state.get_topology();
+    routing::check_host(&graph, source)?;
+    routing::check_host(&graph, destination)?;
+
+    let hops = reliability::most_reliable_route(
+        &graph,
+        state.get_metrics(),
+        source,
+        destination,
+        pdr_source.unwrap_or_default(),
+    )
+    .ok_or_else(|| {
+        NetworkError::InvalidOperation(format!(
+            "No route can deliver fragments from {} to {}",
+            source, destination
+        ))
+    })?;
+    reliability::route_reliability(&graph, state.get_metrics(), hops, fragments.unwrap_or(1))
+}
@@ -1,113 +1,36 @@
 use crate::error::NetworkError;
-use crate::simulation::metrics::{DroneMetrics, HostMetricsTimePoint, Metrics, PacketTypeLabel};
+use crate::simulation::events::{EventPage, EventQuery, LoggedEvent};
+use crate::simulation::listener::shortcuts::ShortcutFailure;
+use crate::simulation::metrics::edges::EdgeStats;
+use crate::simulation::metrics::latency::RouteLatencyStats;
+use crate::simulation::metrics::time_series::{Resolution, SeriesPoint};
+use crate::simulation::metrics::{
+    HostMetricsTimePoint, Metrics, MetricsTimePoint, NackTypeLabel, PacketTypeLabel,
+};
 use crate::simulation::state::SimulationState;
 use crate::simulation::topology::NodeMetadata;
-use crate::utils::ControllerEvent;
-use common_utils::HostEvent;
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tauri::State;
-use wg_2024::controller::DroneEvent;
 use wg_2024::network::NodeId;
 
-/// An event type that wraps the different kinds of events that can be sent.
-#[derive(Serialize, Deserialize, Debug)]
-pub enum EventType {
-    PacketSent,
-    PacketDropped,
-    ControllerShortcut,
-    HostMessageSent,
-}
-
-/// A message sent from the simulation controller.
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Message {
-    pub id: usize,
-    pub event_type: EventType,
-    pub node: NodeId,
-    pub node_type: String, // e.g. "Host" or "Drone"
-    pub packet: String,
-}
-
-/// Returns a list of new messages (already strongly typed) since the provided last ID.
+/// Returns the latest events (at most `max_messages`) logged after `last_seq`.
 #[tauri::command]
 pub fn get_new_messages(
     state: State<Arc<Mutex<SimulationState>>>,
-    last_id: usize,
+    last_seq: u64,
     max_messages: usize,
-) -> Vec<Message> {
-    let state = state.lock();
-    let total_messages = state.get_received_messages().len();
-    let start_index = total_messages.saturating_sub(max_messages).max(last_id);
-
-    if start_index >= total_messages {
-        return Vec::new();
-    }
+) -> Vec<LoggedEvent> {
+    state.lock().get_event_log().since(last_seq, max_messages)
+}
 
-    state.get_received_messages()[start_index..]
-        .iter()
-        .enumerate()
-        .map(|(idx, event)| {
-            let message_id = start_index + idx;
-            match event {
-                ControllerEvent::Drone { node_id, event } => {
-                    let node_type = "Drone".to_string();
-                    match event {
-                        DroneEvent::PacketSent(packet) => Message {
-                            id: message_id,
-                            event_type: EventType::PacketSent,
-                            node: *node_id,
-                            node_type,
-                            packet: format!("{:?}", packet),
-                        },
-                        DroneEvent::PacketDropped(packet) => Message {
-                            id: message_id,
-                            event_type: EventType::PacketDropped,
-                            node: *node_id,
-                            node_type,
-                            packet: format!("{:?}", packet),
-                        },
-                        DroneEvent::ControllerShortcut(packet) => Message {
-                            id: message_id,
-                            event_type: EventType::ControllerShortcut,
-                            node: *node_id,
-                            node_type,
-                            packet: format!("{:?}", packet),
-                        },
-                    }
-                }
-                ControllerEvent::Host { node_id, event } => {
-                    let node_type = "Host".to_string();
-                    match event {
-                        HostEvent::HostMessageSent(_, message, _) => Message {
-                            id: message_id,
-                            event_type: EventType::HostMessageSent,
-                            node: *node_id,
-                            node_type,
-                            packet: format!("{:?}", message),
-                        },
-                        HostEvent::PacketSent(packet_header) => Message {
-                            id: message_id,
-                            event_type: EventType::PacketSent,
-                            node: *node_id,
-                            node_type,
-                            packet: format!("{:?}", packet_header),
-                        },
-                        HostEvent::ControllerShortcut(packet) => Message {
-                            id: message_id,
-                            event_type: EventType::ControllerShortcut,
-                            node: *node_id,
-                            node_type,
-                            packet: format!("{:?}", packet),
-                        },
-                    }
-                }
-            }
-        })
-        .collect()
+/// Returns a page of the logged events matching `query`, with the number of matches.
+#[tauri::command]
+pub fn query_events(state: State<Arc<Mutex<SimulationState>>>, query: EventQuery) -> EventPage {
+    state.lock().get_event_log().query(&query)
 }
 
 /// A strongly typed response for the network infos command.
@@ -137,6 +60,10 @@ pub enum NodeMetrics {
         packets_dropped: u64,
         shortcuts_used: u64,
         packet_type_counts: HashMap<PacketTypeLabel, u64>,
+        /// NACKs originated by the drone, by type
+        nack_type_counts: HashMap<NackTypeLabel, u64>,
+        /// NACKs originated by any drone that reference this drone as error node, by type
+        referenced_in_nacks: HashMap<NackTypeLabel, u64>,
     },
     Host {
         packets_sent: u64,
@@ -190,6 +117,14 @@ fn create_node_metrics(
                 shortcuts_used: drone_metrics.map_or(0, |m| m.shortcuts),
                 packet_type_counts: drone_metrics
                     .map_or_else(HashMap::new, |m| m.packet_type_counts.clone()),
+                nack_type_counts: drone_metrics
+                    .map_or_else(HashMap::new, |m| m.nack_type_counts.clone()),
+                referenced_in_nacks: state
+                    .get_metrics()
+                    .nack_error_nodes
+                    .get(&node_id)
+                    .cloned()
+                    .unwrap_or_default(),
             }
         }
         NodeMetadata::Client | NodeMetadata::Server => {
@@ -248,6 +183,8 @@ pub struct OverviewMetrics {
     total_packets_sent: u64,
     packets_by_type: HashMap<PacketTypeLabel, u64>,
     heatmap: HashMap<String, u64>,
+    nacks_by_type: HashMap<NackTypeLabel, u64>,
+    nacks_by_error_node: HashMap<NodeId, HashMap<NackTypeLabel, u64>>,
 }
 
 /// Returns a strongly typed overview of the network metrics.
@@ -273,6 +210,8 @@ pub fn get_overview_metrics(
         total_packets_sent,
         packets_by_type,
         heatmap,
+        nacks_by_type: metrics.nacks_by_type(),
+        nacks_by_error_node: metrics.nack_error_nodes.clone(),
     })
 }
 
@@ -303,27 +242,47 @@ fn format_heatmap(global_heatmap: &HashMap<(NodeId, NodeId), u64>) -> HashMap<St
         .collect()
 }
 
+/// Drone metrics (including the time series) for a given node.
+#[derive(Serialize, Deserialize)]
+pub struct DroneStats {
+    drops: u64,
+    current_pdr: f32,
+    shortcuts: u64,
+    packet_type_counts: HashMap<PacketTypeLabel, u64>,
+    time_series: Vec<SeriesPoint<MetricsTimePoint>>,
+}
+
 /// Returns the metrics for a given drone node.
 #[tauri::command]
 pub fn get_drone_metrics(
     state: State<Arc<Mutex<SimulationState>>>,
     node_id: NodeId,
-) -> Result<DroneMetrics, NetworkError> {
+) -> Result<DroneStats, NetworkError> {
     let state = state.lock();
-    state
+    let drone_metrics = state
         .get_metrics()
         .drone_metrics
         .get(&node_id)
-        .cloned()
-        .ok_or_else(|| NetworkError::NodeNotFound(node_id.to_string()))
+        .ok_or_else(|| NetworkError::NodeNotFound(node_id.to_string()))?;
+
+    Ok(DroneStats {
+        drops: drone_metrics.drops,
+        current_pdr: drone_metrics.current_pdr,
+        shortcuts: drone_metrics.shortcuts,
+        packet_type_counts: drone_metrics.packet_type_counts.clone(),
+        time_series: drone_metrics
+            .time_series
+            .query(None, None, Resolution::default()),
+    })
 }
 
 /// Host metrics (including latencies and time series) for a given node.
 #[derive(Serialize, Deserialize)]
 pub struct HostStats {
     latencies: Vec<Duration>,
+    route_latencies: Vec<RouteLatencyStats>,
     number_of_fragment_sent: u64,
-    time_series: Vec<HostMetricsTimePoint>,
+    time_series: Vec<SeriesPoint<HostMetricsTimePoint>>,
 }
 
 /// Returns the metrics for a given host node.
@@ -341,7 +300,162 @@ pub fn get_host_metrics(
 
     Ok(HostStats {
         latencies: host_metrics.latencies.clone(),
+        route_latencies: state.get_metrics().route_latency_stats(
+            Some(node_id),
+            None,
+            None,
+            state.get_clock().now_ms(),
+        ),
         number_of_fragment_sent: host_metrics.number_of_fragments_sent(),
-        time_series: host_metrics.time_series.clone(),
+        time_series: host_metrics
+            .time_series
+            .query(None, None, Resolution::default()),
     })
 }
+
+/// Returns the latency percentiles of each host pair.
+///
+/// # Arguments
+///
+/// * `source` - Only consider messages sent by this host, if set.
+/// * `destination` - Only consider messages sent to this host, if set.
+/// * `window_secs` - Only consider the latencies recorded in the last `window_secs` seconds, if set.
+#[tauri::command]
+pub fn get_latency_stats(
+    state: State<Arc<Mutex<SimulationState>>>,
+    source: Option<NodeId>,
+    destination: Option<NodeId>,
+    window_secs: Option<u64>,
+) -> Vec<RouteLatencyStats> {
+    let state = state.lock();
+    state.get_metrics().route_latency_stats(
+        source,
+        destination,
+        window_secs.map(Duration::from_secs),
+        state.get_clock().now_ms(),
+    )
+}
+
+/// The time series of a node, whose points depend on the node type.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "category")]
+pub enum NodeTimeSeries {
+    Drone {
+        points: Vec<SeriesPoint<MetricsTimePoint>>,
+    },
+    Host {
+        points: Vec<SeriesPoint<HostMetricsTimePoint>>,
+    },
+}
+
+/// Returns the time series of a node.
+///
+/// # Arguments
+///
+/// * `node_id` - The node to query.
+/// * `from_ms` - Start of the range (milliseconds since the start of the run), if set.
+/// * `to_ms` - End of the range (milliseconds since the start of the run), if set.
+/// * `resolution` - The size of the buckets, one second by default.
+#[tauri::command]
+pub fn get_time_series(
+    state: State<Arc<Mutex<SimulationState>>>,
+    node_id: NodeId,
+    from_ms: Option<u64>,
+    to_ms: Option<u64>,
+    resolution: Option<Resolution>,
+) -> Result<NodeTimeSeries, NetworkError> {
+    let state = state.lock();
+    let metrics = state.get_metrics();
+    let resolution = resolution.unwrap_or_default();
+
+    if let Some(drone_metrics) = metrics.drone_metrics.get(&node_id) {
+        Ok(NodeTimeSeries::Drone {
+            points: drone_metrics.time_series.query(from_ms, to_ms, resolution),
+        })
+    } else if let Some(host_metrics) = metrics.host_metrics.get(&node_id) {
+        Ok(NodeTimeSeries::Host {
+            points: host_metrics.time_series.query(from_ms, to_ms, resolution),
+        })
+    } else {
+        Err(NetworkError::NodeNotFound(node_id.to_string()))
+    }
+}
+
+/// Returns the metrics of each directed edge.
+///
+/// # Arguments
+///
+/// * `node_id` - Only return the edges starting or ending at this node, if set.
+/// * `resolution` - The size of the utilization buckets, one second by default.
+#[tauri::command]
+pub fn get_edge_metrics(
+    state: State<Arc<Mutex<SimulationState>>>,
+    node_id: Option<NodeId>,
+    resolution: Option<Resolution>,
+) -> Vec<EdgeStats> {
+    let state = state.lock();
+    let metrics = state.get_metrics();
+    let resolution = resolution.unwrap_or_default();
+
+    let mut edges: Vec<EdgeStats> = metrics
+        .edge_metrics
+        .iter()
+        .filter(|((src, dest), _)| node_id.is_none_or(|id| id == *src || id == *dest))
+        .map(|(&edge, edge_metrics)| {
+            let destination_is_drone = metrics.drone_metrics.contains_key(&edge.1);
+            edge_metrics.stats(edge, destination_is_drone, resolution)
+        })
+        .collect();
+
+    edges.sort_by_key(|edge| (edge.source, edge.destination));
+    edges
+}
+
+/// Delivery statistics of the packets sent through the simulation controller to a node.
+#[derive(Serialize, Deserialize)]
+pub struct ShortcutStats {
+    pub destination: NodeId,
+    pub delivered: u64,
+    /// Packets rejected by the listener (invalid type or sender position)
+    pub rejected: u64,
+    /// Packets accepted but not delivered (destination unreachable or disconnected)
+    pub failed: u64,
+    pub failures: HashMap<ShortcutFailure, u64>,
+    pub success_rate: f32,
+}
+
+/// Returns the shortcut delivery statistics of each destination.
+#[tauri::command]
+pub fn get_shortcut_metrics(state: State<Arc<Mutex<SimulationState>>>) -> Vec<ShortcutStats> {
+    let state = state.lock();
+    let mut stats: Vec<ShortcutStats> = state
+        .get_metrics()
+        .shortcut_deliveries
+        .iter()
+        .map(|(&destination, deliveries)| {
+            let rejected: u64 = deliveries
+                .failures
+                .iter()
+                .filter(|(failure, _)| failure.is_rejection())
+                .map(|(_, count)| count)
+                .sum();
+            let failed = deliveries.failures.values().sum::<u64>() - rejected;
+            let total = deliveries.delivered + rejected + failed;
+            ShortcutStats {
+                destination,
+                delivered: deliveries.delivered,
+                rejected,
+                failed,
+                failures: deliveries.failures.clone(),
+                success_rate: if total == 0 {
+                    0.0
+                } else {
+                    deliveries.delivered as f32 / total as f32
+                },
+            }
+        })
+        .collect();
+
+    stats.sort_by_key(|s| s.destination);
+    stats
+}
@@ -1,6 +1,8 @@
+pub mod analysis;
 pub mod config;
 pub mod settings;
 pub mod topology;
 pub mod simulation;
 pub mod controller;
-pub mod metrics;
\ No newline at end of file
+pub mod metrics;
+pub mod runs;
\ No newline at end of file
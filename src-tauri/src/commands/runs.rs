@@ -0,0 +1,51 @@
+use crate::error::NetworkError;
+use crate::simulation::runs::runs_handler::compare_runs as compare_run_summaries;
+use crate::simulation::runs::{RunComparison, RunInfo};
+use crate::simulation::state::SimulationState;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use tauri::State;
+
+/// Returns the list of archived runs, most recent first.
+#[tauri::command]
+pub fn get_archived_runs(state: State<Arc<Mutex<SimulationState>>>) -> Vec<RunInfo> {
+    state
+        .lock()
+        .get_archived_runs()
+        .iter()
+        .rev()
+        .map(|run| run.info())
+        .collect()
+}
+
+/// Compares two archived runs side by side.
+///
+/// # Arguments
+///
+/// * `baseline_run` - The ID of the run used as reference.
+/// * `candidate_run` - The ID of the run compared against the baseline.
+///
+/// # Returns
+///
+/// * `Ok(RunComparison)` - Deltas and significance estimates between the two runs.
+/// * `Err(NetworkError)` - If one of the runs is not in the archive.
+#[tauri::command]
+pub fn compare_runs(
+    state: State<Arc<Mutex<SimulationState>>>,
+    baseline_run: u64,
+    candidate_run: u64,
+) -> Result<RunComparison, NetworkError> {
+    let state = state.lock();
+    let find_run = |run_id: u64| {
+        state
+            .get_archived_runs()
+            .iter()
+            .find(|run| run.run_id == run_id)
+            .ok_or(NetworkError::RunNotFound(run_id))
+    };
+
+    Ok(compare_run_summaries(
+        find_run(baseline_run)?,
+        find_run(candidate_run)?,
+    ))
+}
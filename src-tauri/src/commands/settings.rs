@@ -50,3 +50,18 @@ pub fn set_strict_mode(
     state.lock().set_strict_mode(strict);
     Ok(())
 }
+
+#[tauri::command]
+pub fn get_sniffer_enabled(state: State<Arc<Mutex<SimulationState>>>) -> bool {
+    state.lock().get_sniffer_enabled()
+}
+
+/// Enables the sniffer on the inbound channels of the nodes. Takes effect from the next run.
+#[tauri::command]
+pub fn set_sniffer_enabled(
+    state: State<Arc<Mutex<SimulationState>>>,
+    enabled: bool,
+) -> Result<(), NetworkError> {
+    state.lock().set_sniffer_enabled(enabled);
+    Ok(())
+}
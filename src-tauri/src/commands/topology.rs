@@ -1,12 +1,18 @@
 use crate::error::NetworkError;
+use crate::simulation::controller::transaction::{self, TransactionReport};
+use crate::simulation::journal::journal_handler;
+use crate::simulation::journal::{JournalOperation, JournalView, NodeKind};
 use crate::simulation::state::{SimulationState, SimulationStatus};
-use crate::simulation::topology::NodeMetadata;
+use crate::simulation::topology::connectivity::{self, TopologyAnalysis};
+use crate::simulation::topology::edits::TopologyEdit;
+use crate::simulation::topology::impact::{self, ImpactReport};
+use crate::simulation::topology::routing;
 use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::sync::Arc;
 use tauri::State;
 use wg_2024::network::NodeId;
-use wg_2024::packet::NodeType;
 
 #[tauri::command]
 pub fn remove_node(
@@ -15,27 +21,8 @@ pub fn remove_node(
 ) -> Result<(), NetworkError> {
     let mut sim_state = state.lock();
 
-    if sim_state.get_status() == SimulationStatus::Running {
-        let node_type = sim_state
-            .get_graph()
-            .get_node_type(node_id)
-            .ok_or_else(|| NetworkError::NodeNotFound(node_id.to_string()))?;
-        match node_type {
-            NodeMetadata::Drone(_) => {
-                crate::simulation::controller::crash_drone(&mut sim_state, node_id)?;
-            }
-            NodeMetadata::Client | NodeMetadata::Server => {
-                Err(NetworkError::NodeIsNotDrone(node_id))?;
-            }
-        }
-    } else {
-        crate::simulation::configs::configs_handler::remove_node_from_config(
-            &mut sim_state,
-            node_id,
-        )?;
-    }
-
-    Ok(())
+    let operation = JournalOperation::remove_node(&sim_state.get_topology(), node_id)?;
+    journal_handler::execute(&mut sim_state, operation)
 }
 
 #[tauri::command]
@@ -46,17 +33,10 @@ pub fn remove_edge(
 ) -> Result<(), NetworkError> {
     let mut sim_state = state.lock();
 
-    if sim_state.get_status() == SimulationStatus::Running {
-        crate::simulation::controller::remove_edge(&mut sim_state, node1_id, node2_id)?;
-    } else {
-        crate::simulation::configs::configs_handler::remove_edge_from_config(
-            &mut sim_state,
-            node1_id,
-            node2_id,
-        )?;
-    }
-
-    Ok(())
+    journal_handler::execute(
+        &mut sim_state,
+        JournalOperation::RemoveEdge { node1_id, node2_id },
+    )
 }
 
 #[tauri::command]
@@ -69,31 +49,25 @@ pub fn add_node(
     let mut sim_state = state.lock();
 
     let node_type = match node_type.as_str() {
-        "Drone" => NodeType::Drone,
-        "Client" => NodeType::Client,
-        "Server" => NodeType::Server,
+        "Drone" => NodeKind::Drone,
+        "Client" => NodeKind::Client,
+        "Server" => NodeKind::Server,
         _ => return Err(NetworkError::InvalidNodeType(node_type)),
     };
+    if node_type == NodeKind::Drone && pdr.is_none() {
+        return Err(NetworkError::InvalidPdr(0));
+    }
 
-    if sim_state.get_status() == SimulationStatus::Running {
-        if node_type == NodeType::Drone {
-            let pdr = pdr.ok_or(NetworkError::InvalidPdr(0))?;
-            crate::simulation::controller::add_drone(&mut sim_state, neighbors, pdr)?;
-        } else {
-            return Err(NetworkError::InvalidOperation(
-                "Cannot add a client or server node during the simulation".to_string(),
-            ));
-        }
-    } else {
-        crate::simulation::configs::configs_handler::add_node_to_config(
-            &mut sim_state,
+    let node_id = journal_handler::next_node_id(&sim_state)?;
+    journal_handler::execute(
+        &mut sim_state,
+        JournalOperation::AddNode {
+            node_id,
             node_type,
             neighbors,
             pdr,
-        )?;
-    }
-
-    Ok(())
+        },
+    )
 }
 
 #[tauri::command]
@@ -104,17 +78,10 @@ pub fn add_edge(
 ) -> Result<(), NetworkError> {
     let mut sim_state = state.lock();
 
-    if sim_state.get_status() == SimulationStatus::Running {
-        crate::simulation::controller::add_edge(&mut sim_state, node1_id, node2_id)?;
-    } else {
-        crate::simulation::configs::configs_handler::add_edge_to_config(
-            &mut sim_state,
-            node1_id,
-            node2_id,
-        )?;
-    }
-
-    Ok(())
+    journal_handler::execute(
+        &mut sim_state,
+        JournalOperation::AddEdge { node1_id, node2_id },
+    )
 }
 
 #[tauri::command]
@@ -246,3 +213,202 @@ pub fn get_network_nodes(state: State<Arc<Mutex<SimulationState>>>) -> Result<Va
 
     Ok(json!(nodes_json))
 }
+
+/// Number of routes suggested when neither `k` nor `max_hops` is given.
+const DEFAULT_SUGGESTED_ROUTES: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestedRoute {
+    /// The route, from the source to the destination, usable as the hops of `send_packet`
+    pub hops: Vec<NodeId>,
+    /// Number of hops of the route
+    pub length: usize,
+}
+
+/// Suggests routes between two hosts, on the running network or on the loaded configuration.
+///
+/// # Arguments
+///
+/// * `k` - Return the `k` shortest routes. Defaults to 3.
+/// * `max_hops` - Return every loopless route with at most this many hops instead, shortest
+///   first. With `k`, only the first `k` of them are returned.
+#[tauri::command]
+pub fn suggest_routes(
+    state: State<Arc<Mutex<SimulationState>>>,
+    source: NodeId,
+    destination: NodeId,
+    k: Option<usize>,
+    max_hops: Option<usize>,
+) -> Result<Vec<SuggestedRoute>, NetworkError> {
+    let sim_state = state.lock();
+    let graph = sim_state.get_topology();
+    routing::check_host(&graph, source)?;
+    routing::check_host(&graph, destination)?;
+
+    let routes = match max_hops {
+        Some(max_hops) => {
+            let mut routes = routing::simple_paths(&graph, source, destination, max_hops);
+            if let Some(k) = k {
+                routes.truncate(k);
+            }
+            routes
+        }
+        None => routing::k_shortest_paths(
+            &graph,
+            source,
+            destination,
+            k.unwrap_or(DEFAULT_SUGGESTED_ROUTES),
+        ),
+    };
+
+    Ok(routes
+        .into_iter()
+        .map(|hops| SuggestedRoute {
+            length: hops.len() - 1,
+            hops,
+        })
+        .collect())
+}
+
+/// Returns the critical nodes and links of the running network, or of the loaded
+/// configuration, and the drones that cannot be crashed.
+#[tauri::command]
+pub fn get_topology_analysis(state: State<Arc<Mutex<SimulationState>>>) -> TopologyAnalysis {
+    // The analysis runs many max-flows: release the lock first
+    let (graph, strict) = {
+        let sim_state = state.lock();
+        (
+            sim_state.get_topology().into_owned(),
+            sim_state.get_strict_mode(),
+        )
+    };
+    connectivity::analyze_topology(&graph, strict)
+}
+
+/// Returns the impact of `edits` on the running network, or on the loaded configuration,
+/// without applying them.
+fn preview_edits(
+    sim_state: &SimulationState,
+    edits: &[TopologyEdit],
+) -> Result<ImpactReport, NetworkError> {
+    impact::preview(
+        &sim_state.get_topology(),
+        sim_state.get_strict_mode(),
+        edits,
+        &sim_state.get_journey_tracker().observed_routes(),
+    )
+}
+
+#[tauri::command]
+pub fn preview_crash_drone(
+    state: State<Arc<Mutex<SimulationState>>>,
+    drone_id: NodeId,
+) -> Result<ImpactReport, NetworkError> {
+    preview_edits(&state.lock(), &[TopologyEdit::CrashDrone { drone_id }])
+}
+
+#[tauri::command]
+pub fn preview_remove_edge(
+    state: State<Arc<Mutex<SimulationState>>>,
+    node1_id: NodeId,
+    node2_id: NodeId,
+) -> Result<ImpactReport, NetworkError> {
+    preview_edits(
+        &state.lock(),
+        &[TopologyEdit::RemoveEdge { node1_id, node2_id }],
+    )
+}
+
+#[tauri::command]
+pub fn preview_add_edge(
+    state: State<Arc<Mutex<SimulationState>>>,
+    node1_id: NodeId,
+    node2_id: NodeId,
+) -> Result<ImpactReport, NetworkError> {
+    preview_edits(
+        &state.lock(),
+        &[TopologyEdit::AddEdge { node1_id, node2_id }],
+    )
+}
+
+#[tauri::command]
+pub fn preview_add_drone(
+    state: State<Arc<Mutex<SimulationState>>>,
+    neighbors: Vec<NodeId>,
+    pdr: u32,
+) -> Result<ImpactReport, NetworkError> {
+    preview_edits(&state.lock(), &[TopologyEdit::AddDrone { neighbors, pdr }])
+}
+
+/// Applies `edits` as a whole to the running network, or to the loaded configuration. Only
+/// the final topology is validated, and nothing is changed if an edit fails, but for the
+/// drones already crashed.
+///
+/// Transactions are not recorded in the journal. Once the topology changed, its operations
+/// could not be undone on the new topology: a runtime transaction discards the runtime
+/// operations, and a configuration transaction clears the whole journal.
+#[tauri::command]
+pub fn apply_topology_transaction(
+    state: State<Arc<Mutex<SimulationState>>>,
+    edits: Vec<TopologyEdit>,
+) -> Result<TransactionReport, NetworkError> {
+    let mut sim_state = state.lock();
+
+    let running = sim_state.get_status() == SimulationStatus::Running;
+    let result = if running {
+        transaction::apply_transaction(&mut sim_state, &edits)
+    } else {
+        transaction::apply_config_transaction(&mut sim_state, &edits)
+    };
+    let changed = match &result {
+        Ok(_) => !edits.is_empty(),
+        Err(NetworkError::RolledBack {
+            not_rolled_back, ..
+        }) => !not_rolled_back.is_empty(),
+        Err(_) => false,
+    };
+    if changed {
+        if running {
+            sim_state.get_journal_mut().discard_runtime_entries();
+        } else {
+            sim_state.get_journal_mut().clear();
+        }
+    }
+    result
+}
+
+/// Returns the topology edits that can be undone and redone.
+#[tauri::command]
+pub fn get_journal(state: State<Arc<Mutex<SimulationState>>>) -> JournalView {
+    state.lock().get_journal().view()
+}
+
+/// Undoes the last topology edit and returns it.
+#[tauri::command]
+pub fn undo_topology_edit(
+    state: State<Arc<Mutex<SimulationState>>>,
+) -> Result<JournalOperation, NetworkError> {
+    journal_handler::undo(&mut state.lock())
+}
+
+/// Applies again the last undone topology edit and returns it.
+#[tauri::command]
+pub fn redo_topology_edit(
+    state: State<Arc<Mutex<SimulationState>>>,
+) -> Result<JournalOperation, NetworkError> {
+    journal_handler::redo(&mut state.lock())
+}
+
+/// Writes the topology edits that can be undone to `path` as a JSON scenario script.
+/// Returns the number of steps written.
+#[tauri::command]
+pub fn export_scenario(
+    state: State<Arc<Mutex<SimulationState>>>,
+    path: String,
+) -> Result<usize, NetworkError> {
+    let scenario = state.lock().get_journal().scenario();
+    let json =
+        serde_json::to_string_pretty(&scenario).map_err(|e| NetworkError::Other(e.to_string()))?;
+    std::fs::write(&path, json).map_err(|e| NetworkError::PathError(e.to_string()))?;
+    Ok(scenario.steps.len())
+}
@@ -1,3 +1,4 @@
+use crate::simulation::controller::side_effects::{describe, SideEffect};
 use serde::ser::{Serialize, Serializer};
 use thiserror::Error;
 use wg_2024::network::NodeId;
@@ -63,6 +64,28 @@ pub enum NetworkError {
 
     #[error("Invalid node type: {0}")]
     InvalidNodeType(String),
+
+    #[error("The selected run is not in the archive: {0}")]
+    RunNotFound(u64),
+
+    #[error("No packet observed for {0}")]
+    PacketNotFound(String),
+
+    #[error("The selected injected session does not exist: {0}")]
+    InjectionNotFound(u64),
+
+    #[error(
+        "{error} (rolled back: {}; could not roll back: {})",
+        describe(.rolled_back),
+        describe(.not_rolled_back)
+    )]
+    RolledBack {
+        error: Box<NetworkError>,
+        /// The side effects undone, most recent first
+        rolled_back: Vec<SideEffect>,
+        /// The side effects left in place because undoing them failed
+        not_rolled_back: Vec<SideEffect>,
+    },
 }
 
 impl Serialize for NetworkError {
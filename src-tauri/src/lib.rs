@@ -58,11 +58,16 @@ pub fn run() {
             crate::commands::controller::crash_drone,
             crate::commands::controller::set_pdr,
             crate::commands::controller::send_packet,
+            crate::commands::controller::send_packet_batch,
+            crate::commands::controller::get_injected_sessions,
+            crate::commands::controller::cancel_injection,
             // settings
             crate::commands::settings::set_discovery_interval,
             crate::commands::settings::get_discovery_interval,
             crate::commands::settings::get_strict_mode,
             crate::commands::settings::set_strict_mode,
+            crate::commands::settings::get_sniffer_enabled,
+            crate::commands::settings::set_sniffer_enabled,
             // simulation
             crate::commands::simulation::start_simulation,
             crate::commands::simulation::get_simulation_status,
@@ -74,13 +79,40 @@ pub fn run() {
             crate::commands::topology::add_edge,
             crate::commands::topology::get_graph, // da finire il refactoring
             crate::commands::topology::get_network_nodes, // da finire il refactoring
+            crate::commands::topology::suggest_routes,
+            crate::commands::topology::get_topology_analysis,
+            crate::commands::topology::preview_crash_drone,
+            crate::commands::topology::preview_remove_edge,
+            crate::commands::topology::preview_add_edge,
+            crate::commands::topology::preview_add_drone,
+            crate::commands::topology::apply_topology_transaction,
+            crate::commands::topology::get_journal,
+            crate::commands::topology::undo_topology_edit,
+            crate::commands::topology::redo_topology_edit,
+            crate::commands::topology::export_scenario,
             // metrics
             crate::commands::metrics::get_new_messages,
+            crate::commands::metrics::query_events,
             crate::commands::metrics::get_network_infos,
             crate::commands::metrics::get_node_info,
             crate::commands::metrics::get_overview_metrics,
             crate::commands::metrics::get_drone_metrics,
             crate::commands::metrics::get_host_metrics,
+            crate::commands::metrics::get_latency_stats,
+            crate::commands::metrics::get_time_series,
+            crate::commands::metrics::get_edge_metrics,
+            crate::commands::metrics::get_shortcut_metrics,
+            // analysis
+            crate::commands::analysis::get_flood_analysis,
+            crate::commands::analysis::get_sessions,
+            crate::commands::analysis::get_packet_journey,
+            crate::commands::analysis::export_sequence_diagram,
+            crate::commands::analysis::export_pcapng,
+            crate::commands::analysis::get_route_reliability,
+            crate::commands::analysis::get_most_reliable_route,
+            // runs
+            crate::commands::runs::get_archived_runs,
+            crate::commands::runs::compare_runs,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,39 @@
+use crate::simulation::clock::SimulationClock;
+use crate::utils::now_millis;
+use std::time::{Duration, Instant};
+
+impl SimulationClock {
+    /// Restarts the clock from zero.
+    pub fn start(&mut self) {
+        self.started = Some(Instant::now());
+        self.started_at = now_millis();
+        self.stopped = None;
+    }
+
+    /// Freezes the clock at the end of the run, so that the metrics of the run can still
+    /// be read with consistent timestamps.
+    pub fn stop(&mut self) {
+        if self.started.is_some() && self.stopped.is_none() {
+            self.stopped = Some(Instant::now());
+        }
+    }
+
+    /// Time elapsed since the start of the run, up to its end if it was stopped.
+    pub fn elapsed(&self) -> Duration {
+        let Some(started) = self.started else {
+            return Duration::ZERO;
+        };
+        let now = self.stopped.unwrap_or_else(Instant::now);
+        now.saturating_duration_since(started)
+    }
+
+    /// Run-relative time in milliseconds.
+    pub fn now_ms(&self) -> u64 {
+        self.elapsed().as_millis() as u64
+    }
+
+    /// UNIX timestamp in milliseconds of the start of the run.
+    pub fn started_at(&self) -> u64 {
+        self.started_at
+    }
+}
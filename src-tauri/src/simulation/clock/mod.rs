@@ -0,0 +1,18 @@
+pub mod clock_handler;
+
+use std::time::Instant;
+
+/// Run-relative time of the simulation.
+///
+/// The clock is started at `start_simulation` and measures the time elapsed since then
+/// with a monotonic, high resolution source. Before the first run, and after a run is
+/// stopped, the clock is frozen.
+#[derive(Debug, Default)]
+pub struct SimulationClock {
+    /// Instant at which the current run was started
+    started: Option<Instant>,
+    /// UNIX timestamp in milliseconds of the start of the current run
+    started_at: u64,
+    /// Instant at which the current run was stopped, if it was
+    stopped: Option<Instant>,
+}
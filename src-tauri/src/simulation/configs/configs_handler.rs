@@ -112,21 +112,38 @@ pub fn remove_edge_from_config(
     Ok(())
 }
 
-pub fn add_node_to_config(
+/// Returns the first ID not used by any client, server or drone of the configuration.
+pub fn next_config_node_id(state: &SimulationState) -> Result<NodeId, NetworkError> {
+    let config = state.get_config().ok_or(NetworkError::NoConfigLoaded)?;
+
+    let mut node_id = 1;
+    while config_has_node(config, node_id) {
+        node_id += 1;
+    }
+    Ok(node_id)
+}
+
+fn config_has_node(config: &Config, node_id: NodeId) -> bool {
+    config.client.iter().any(|c| c.id == node_id)
+        || config.server.iter().any(|s| s.id == node_id)
+        || config.drone.iter().any(|d| d.id == node_id)
+}
+
+/// Adds a node with the given ID, which must not be in use.
+pub fn insert_node_into_config(
     state: &mut SimulationState,
+    node_id: NodeId,
     node_type: NodeType,
     neighbors: Vec<NodeId>,
     pdr: Option<u32>,
 ) -> Result<(), NetworkError> {
     let config = state.get_config_mut().ok_or(NetworkError::NoConfigLoaded)?;
 
-    // find first available node id (that is not any client, server or drone)
-    let mut node_id = 1;
-    while config.client.iter().any(|c| c.id == node_id)
-        || config.server.iter().any(|s| s.id == node_id)
-        || config.drone.iter().any(|d| d.id == node_id)
-    {
-        node_id += 1;
+    if config_has_node(config, node_id) {
+        return Err(NetworkError::InvalidOperation(format!(
+            "Node {} already exists",
+            node_id
+        )));
     }
 
     match node_type {
@@ -176,6 +193,26 @@ pub fn add_node_to_config(
     Ok(())
 }
 
+pub fn set_pdr_in_config(
+    state: &mut SimulationState,
+    drone_id: NodeId,
+    pdr: u8,
+) -> Result<(), NetworkError> {
+    if pdr > 100 {
+        return Err(NetworkError::InvalidPdr(pdr));
+    }
+    let config = state.get_config_mut().ok_or(NetworkError::NoConfigLoaded)?;
+
+    let drone = config
+        .drone
+        .iter_mut()
+        .find(|d| d.id == drone_id)
+        .ok_or(NetworkError::NodeIsNotDrone(drone_id))?;
+    drone.pdr = pdr as f32 / 100.0;
+
+    Ok(())
+}
+
 pub fn add_edge_to_config(
     state: &mut SimulationState,
     node1_id: NodeId,
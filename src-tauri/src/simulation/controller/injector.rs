@@ -0,0 +1,290 @@
+use crate::error::NetworkError;
+use crate::simulation::controller::packet_injection::{inject_packet, PacketSpec, PayloadSpec};
+use crate::simulation::state::SimulationState;
+use log::warn;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{Packet, FRAGMENT_DSIZE};
+
+/// Maximum number of injected sessions kept by the tracker. The oldest session is evicted first.
+const MAX_INJECTED_SESSIONS: usize = 1_000;
+
+/// First session ID given to injected sessions, far above the IDs generated by the hosts.
+const FIRST_INJECTED_SESSION_ID: u64 = 1 << 48;
+
+/// Maximum number of packets in a batch.
+const MAX_BATCH_PACKETS: usize = 100_000;
+
+/// A batch of packets sent by `hops[0]` along `hops`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchSpec {
+    pub hops: Vec<NodeId>,
+    pub payload: BatchPayload,
+    /// Packets per second. Every packet is sent at once when missing.
+    pub rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum BatchPayload {
+    /// The same packet, `count` times
+    Repeat { payload: PayloadSpec, count: u32 },
+    /// A payload of any length, split in fragments of `FRAGMENT_DSIZE` bytes
+    Message { data: Vec<u8> },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InjectionStatus {
+    Running,
+    Completed,
+    Cancelled,
+    Failed(String),
+}
+
+/// The progress of a batch injected by the simulation controller.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InjectedSession {
+    pub session_id: u64,
+    pub source: NodeId,
+    pub route: Vec<NodeId>,
+    pub total_packets: usize,
+    pub sent_packets: usize,
+    /// Run-relative timestamp in milliseconds at which the injection started
+    pub started_at: u64,
+    /// Run-relative timestamp in milliseconds at which the injection stopped
+    pub finished_at: Option<u64>,
+    pub status: InjectionStatus,
+}
+
+/// Keeps the sessions injected during the current run, and hands out their IDs.
+#[derive(Debug)]
+pub struct InjectionTracker {
+    sessions: HashMap<u64, InjectedSession>,
+    /// IDs of the sessions, in the order they were started
+    order: VecDeque<u64>,
+    next_session_id: u64,
+}
+
+impl Default for InjectionTracker {
+    fn default() -> Self {
+        Self {
+            sessions: HashMap::new(),
+            order: VecDeque::new(),
+            next_session_id: FIRST_INJECTED_SESSION_ID,
+        }
+    }
+}
+
+impl InjectionTracker {
+    pub fn next_session_id(&mut self) -> u64 {
+        let session_id = self.next_session_id;
+        self.next_session_id += 1;
+        session_id
+    }
+
+    pub fn start(&mut self, session: InjectedSession) {
+        self.order.push_back(session.session_id);
+        if self.order.len() > MAX_INJECTED_SESSIONS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.sessions.remove(&oldest);
+            }
+        }
+        self.sessions.insert(session.session_id, session);
+    }
+
+    pub fn is_running(&self, session_id: u64) -> bool {
+        self.sessions
+            .get(&session_id)
+            .is_some_and(|s| s.status == InjectionStatus::Running)
+    }
+
+    pub fn record_sent(&mut self, session_id: u64) {
+        if let Some(session) = self.sessions.get_mut(&session_id) {
+            session.sent_packets += 1;
+        }
+    }
+
+    pub fn finish(&mut self, session_id: u64, status: InjectionStatus, now_ms: u64) {
+        if let Some(session) = self.sessions.get_mut(&session_id) {
+            if session.status == InjectionStatus::Running {
+                session.status = status;
+                session.finished_at = Some(now_ms);
+            }
+        }
+    }
+
+    pub fn session(&self, session_id: u64) -> Option<&InjectedSession> {
+        self.sessions.get(&session_id)
+    }
+
+    /// Returns the injected sessions, most recent first.
+    pub fn sessions(&self) -> Vec<InjectedSession> {
+        self.order
+            .iter()
+            .rev()
+            .filter_map(|id| self.sessions.get(id).cloned())
+            .collect()
+    }
+}
+
+impl BatchSpec {
+    /// Builds the packets of the batch, in sending order.
+    pub fn build_packets(&self, session_id: u64) -> Result<Vec<Packet>, NetworkError> {
+        if self.hops.len() < 2 {
+            return Err(NetworkError::ValidationError(
+                "A batch needs a route with at least a sender and a next hop".into(),
+            ));
+        }
+
+        let total_packets = match &self.payload {
+            BatchPayload::Repeat { count, .. } => *count as usize,
+            BatchPayload::Message { data } => data.chunks(FRAGMENT_DSIZE).len(),
+        };
+        if total_packets > MAX_BATCH_PACKETS {
+            return Err(NetworkError::ValidationError(format!(
+                "A batch cannot have more than {} packets, got {}",
+                MAX_BATCH_PACKETS, total_packets
+            )));
+        }
+
+        let payloads = match &self.payload {
+            BatchPayload::Repeat { payload, count } => vec![payload.clone(); *count as usize],
+            BatchPayload::Message { data } => {
+                if data.is_empty() {
+                    return Err(NetworkError::ValidationError(
+                        "Cannot send an empty message".into(),
+                    ));
+                }
+                let total_n_fragments = total_packets as u64;
+                data.chunks(FRAGMENT_DSIZE)
+                    .enumerate()
+                    .map(|(index, chunk)| PayloadSpec::MsgFragment {
+                        fragment_index: index as u64,
+                        total_n_fragments,
+                        data: chunk.to_vec(),
+                        length: None,
+                    })
+                    .collect()
+            }
+        };
+
+        payloads
+            .into_iter()
+            .map(|payload| {
+                PacketSpec {
+                    session_id,
+                    hops: self.hops.clone(),
+                    hop_index: 1,
+                    payload,
+                    inject_into: None,
+                }
+                .into_packet()
+            })
+            .collect()
+    }
+
+    /// Time to wait between two packets.
+    pub fn interval(&self) -> Result<Option<Duration>, NetworkError> {
+        match self.rate {
+            None => Ok(None),
+            // Rates too low give an interval a `Duration` cannot hold
+            Some(rate) if rate.is_finite() && rate > 0.0 => Duration::try_from_secs_f64(1.0 / rate)
+                .map(Some)
+                .map_err(|_| NetworkError::ValidationError(format!("Rate {} is too low", rate))),
+            Some(rate) => Err(NetworkError::ValidationError(format!(
+                "Invalid rate {}: it must be a positive number of packets per second",
+                rate
+            ))),
+        }
+    }
+}
+
+/// Registers the batch as a new injected session and starts sending it from a dedicated thread.
+///
+/// The packets are sent on the inbound channel of `hops[1]`, as if `hops[0]` had sent them.
+/// The injection stops early when it is cancelled, when a packet cannot be sent, or when the
+/// run it was started in is over.
+pub fn start_injection(
+    state: &Arc<Mutex<SimulationState>>,
+    batch: BatchSpec,
+) -> Result<InjectedSession, NetworkError> {
+    let mut sim_state = state.lock();
+    let interval = batch.interval()?;
+    let session_id = sim_state.get_injection_tracker_mut().next_session_id();
+    let packets = batch.build_packets(session_id)?;
+    let target = batch.hops[1];
+    if !sim_state.get_inter_node_channels().contains_key(&target) {
+        return Err(NetworkError::ChannelNotFound(target));
+    }
+
+    let session = InjectedSession {
+        session_id,
+        source: batch.hops[0],
+        route: batch.hops,
+        total_packets: packets.len(),
+        sent_packets: 0,
+        started_at: sim_state.get_clock().now_ms(),
+        finished_at: None,
+        status: InjectionStatus::Running,
+    };
+    sim_state.get_injection_tracker_mut().start(session.clone());
+    let run_id = sim_state.get_run_id();
+    drop(sim_state);
+
+    let state = Arc::clone(state);
+    thread::spawn(move || {
+        for (index, packet) in packets.into_iter().enumerate() {
+            if index > 0 {
+                if let Some(interval) = interval {
+                    thread::sleep(interval);
+                }
+            }
+
+            let mut state = state.lock();
+            if state.get_run_id() != run_id || !state.get_injection_tracker().is_running(session_id)
+            {
+                return;
+            }
+            let now = state.get_clock().now_ms();
+            match inject_packet(&state, target, packet) {
+                Ok(()) => state.get_injection_tracker_mut().record_sent(session_id),
+                Err(err) => {
+                    warn!("Injection of session {} failed: {}", session_id, err);
+                    state.get_injection_tracker_mut().finish(
+                        session_id,
+                        InjectionStatus::Failed(err.to_string()),
+                        now,
+                    );
+                    return;
+                }
+            }
+        }
+
+        let mut state = state.lock();
+        if state.get_run_id() == run_id {
+            let now = state.get_clock().now_ms();
+            state
+                .get_injection_tracker_mut()
+                .finish(session_id, InjectionStatus::Completed, now);
+        }
+    });
+
+    Ok(session)
+}
+
+/// Stops a running injection before its next packet.
+pub fn cancel_injection(state: &mut SimulationState, session_id: u64) -> Result<(), NetworkError> {
+    if state.get_injection_tracker().session(session_id).is_none() {
+        return Err(NetworkError::InjectionNotFound(session_id));
+    }
+    let now = state.get_clock().now_ms();
+    state
+        .get_injection_tracker_mut()
+        .finish(session_id, InjectionStatus::Cancelled, now);
+    Ok(())
+}
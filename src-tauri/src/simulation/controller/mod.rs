@@ -1,41 +1,55 @@
 use crate::error::NetworkError;
-use crate::simulation::controller::controller_commands::send_set_pdr_command;
+use crate::simulation::controller::controller_commands::{
+    send_crash_command, send_set_pdr_command,
+};
+use crate::simulation::controller::side_effects::{
+    add_sender, remove_sender, rollback, SideEffect,
+};
 use crate::simulation::state::SimulationState;
 use crate::simulation::topology::validations::validate_graph;
 use wg_2024::network::NodeId;
 
 pub mod controller_commands;
+pub mod injector;
+pub mod packet_injection;
+pub mod side_effects;
+pub mod transaction;
 
 pub fn crash_drone(state: &mut SimulationState, drone_id: NodeId) -> Result<(), NetworkError> {
     // Validation
     let mut new_graph = state.get_graph().clone();
     new_graph.crash_drone(drone_id)?;
     validate_graph(&new_graph, state.get_strict_mode())?;
+    // The crash cannot be undone: make sure it can be sent before changing anything
+    if !state
+        .get_drone_controller_channels()
+        .contains_key(&drone_id)
+    {
+        return Err(NetworkError::ChannelNotFound(drone_id));
+    }
 
     // Send RemoveSender command to neighbors
+    let mut effects = Vec::new();
     for neighbor in state.get_graph().get_neighbors(drone_id) {
-        crate::simulation::controller::controller_commands::send_remove_sender_command(
-            state, neighbor, drone_id,
-        )?;
+        if let Err(err) = remove_sender(state, neighbor, drone_id, &mut effects) {
+            return Err(rollback(state, &effects, err));
+        }
+    }
+    if let Err(err) = send_crash_command(state, drone_id) {
+        return Err(rollback(state, &effects, err));
     }
-
-    state.get_inter_node_channels_mut().remove(&drone_id);
-    crate::simulation::controller::controller_commands::send_crash_command(state, drone_id)?;
 
     // Update the graph
+    state.get_inter_node_channels_mut().remove(&drone_id);
     state.set_graph(new_graph);
-
     state.get_drone_controller_channels_mut().remove(&drone_id);
 
     // Join the drone thread
-    match state
-        .get_nodes_threads_mut()
-        .remove(&drone_id)
-        .unwrap()
-        .join()
-    {
-        Ok(_) => Ok(()),
-        Err(_) => Err(NetworkError::ThreadJoinError(drone_id.to_string())),
+    match state.get_nodes_threads_mut().remove(&drone_id) {
+        Some(handle) => handle
+            .join()
+            .map_err(|_| NetworkError::ThreadJoinError(drone_id.to_string())),
+        None => Ok(()),
     }
 }
 
@@ -44,9 +58,13 @@ pub fn set_pdr(state: &mut SimulationState, drone_id: NodeId, pdr: u8) -> Result
         return Err(NetworkError::InvalidPdr(pdr));
     }
 
-    send_set_pdr_command(&state, drone_id, pdr)?;
+    // Single command: checking the graph first leaves nothing to undo
+    let mut new_graph = state.get_graph().clone();
+    new_graph.set_pdr(drone_id, pdr)?;
 
-    state.get_graph_mut().set_pdr(drone_id, pdr)?;
+    send_set_pdr_command(state, drone_id, pdr)?;
+
+    state.set_graph(new_graph);
 
     Ok(())
 }
@@ -68,12 +86,12 @@ pub fn remove_edge(
     validate_graph(&new_graph, state.get_strict_mode())?;
 
     // Send RemoveSender command to neighbors
-    crate::simulation::controller::controller_commands::send_remove_sender_command(
-        &state, node1_id, node2_id,
-    )?;
-    crate::simulation::controller::controller_commands::send_remove_sender_command(
-        &state, node2_id, node1_id,
-    )?;
+    let mut effects = Vec::new();
+    if let Err(err) = remove_sender(state, node1_id, node2_id, &mut effects)
+        .and_then(|()| remove_sender(state, node2_id, node1_id, &mut effects))
+    {
+        return Err(rollback(state, &effects, err));
+    }
 
     // Update the graph
     state.set_graph(new_graph);
@@ -97,34 +115,55 @@ pub fn add_edge(
     new_graph.add_edge(node1_id, node2_id)?;
     validate_graph(&new_graph, state.get_strict_mode())?;
 
-    crate::simulation::controller::controller_commands::send_add_sender_command(
-        state, node1_id, node2_id,
-    )?;
-    crate::simulation::controller::controller_commands::send_add_sender_command(
-        state, node2_id, node1_id,
-    )?;
+    let mut effects = Vec::new();
+    if let Err(err) = add_sender(state, node1_id, node2_id, &mut effects)
+        .and_then(|()| add_sender(state, node2_id, node1_id, &mut effects))
+    {
+        return Err(rollback(state, &effects, err));
+    }
 
     state.set_graph(new_graph);
 
     Ok(())
 }
 
-pub fn add_drone(
+/// Adds a drone with the given ID, which must not be in use.
+pub fn add_drone_with_id(
     state: &mut SimulationState,
+    node_id: NodeId,
     neighbors: Vec<NodeId>,
     pdr: u32,
 ) -> Result<(), NetworkError> {
-    let mut new_graph = state.get_graph().clone();
-    let node_id = new_graph.get_next_node_id();
+    let old_graph = state.get_graph().clone();
+    let mut new_graph = old_graph.clone();
     new_graph.add_drone(node_id, neighbors.clone(), pdr)?;
 
     validate_graph(&new_graph, state.get_strict_mode())?;
 
+    // The commands look the nodes up in the graph, which must know the new drone
     state.set_graph(new_graph);
 
-    crate::simulation::initializer::network_initializer::create_new_drone(
-        state, node_id, neighbors, pdr,
-    )?;
+    // Recorded first: a drone that fails to start still leaves channels and metrics behind
+    let mut effects = vec![SideEffect::SpawnedDrone { drone_id: node_id }];
+    let result = crate::simulation::initializer::network_initializer::create_new_drone(
+        state,
+        node_id,
+        vec![],
+        pdr,
+    )
+    .and_then(|_| {
+        // Both sides of every link need the sender of the other
+        for &neighbor in &neighbors {
+            add_sender(state, node_id, neighbor, &mut effects)?;
+            add_sender(state, neighbor, node_id, &mut effects)?;
+        }
+        Ok(())
+    });
+    if let Err(err) = result {
+        let err = rollback(state, &effects, err);
+        state.set_graph(old_graph);
+        return Err(err);
+    }
 
     Ok(())
 }
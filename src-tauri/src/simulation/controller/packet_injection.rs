@@ -0,0 +1,157 @@
+use crate::error::NetworkError;
+use crate::simulation::state::SimulationState;
+use serde::{Deserialize, Serialize};
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{
+    Ack, FloodRequest, FloodResponse, Fragment, Nack, NackType, NodeType, Packet, PacketType,
+    FRAGMENT_DSIZE,
+};
+
+/// Full description of a packet to inject in the network.
+///
+/// Nothing is inferred: the routing header is used exactly as given, so that malformed or
+/// unexpected packets can be sent on purpose.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PacketSpec {
+    pub session_id: u64,
+    pub hops: Vec<NodeId>,
+    pub hop_index: usize,
+    pub payload: PayloadSpec,
+    /// The node whose inbound channel receives the packet. Defaults to `hops[hop_index]`.
+    pub inject_into: Option<NodeId>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum PayloadSpec {
+    MsgFragment {
+        fragment_index: u64,
+        total_n_fragments: u64,
+        /// Raw content of the fragment, at most `FRAGMENT_DSIZE` bytes
+        data: Vec<u8>,
+        /// Defaults to the length of `data`
+        length: Option<u8>,
+    },
+    Ack {
+        fragment_index: u64,
+    },
+    Nack {
+        fragment_index: u64,
+        nack_type: NackType,
+    },
+    FloodRequest {
+        flood_id: u64,
+        initiator_id: NodeId,
+        initiator_type: NodeType,
+        /// Defaults to the initiator alone
+        path_trace: Option<Vec<(NodeId, NodeType)>>,
+    },
+    FloodResponse {
+        flood_id: u64,
+        path_trace: Vec<(NodeId, NodeType)>,
+    },
+}
+
+impl PacketSpec {
+    /// Builds the packet described by the spec.
+    pub fn into_packet(self) -> Result<Packet, NetworkError> {
+        if self.hop_index > self.hops.len() {
+            return Err(NetworkError::ValidationError(format!(
+                "Invalid hop_index {} for a route of length {}",
+                self.hop_index,
+                self.hops.len()
+            )));
+        }
+
+        Ok(Packet {
+            routing_header: SourceRoutingHeader::new(self.hops, self.hop_index),
+            session_id: self.session_id,
+            pack_type: self.payload.into_packet_type()?,
+        })
+    }
+
+    /// The node that receives the packet when it is injected.
+    pub fn injection_point(&self) -> Result<NodeId, NetworkError> {
+        self.inject_into
+            .or_else(|| self.hops.get(self.hop_index).copied())
+            .ok_or_else(|| {
+                NetworkError::ValidationError(
+                    "No injection point: set inject_into or a hop_index inside the route".into(),
+                )
+            })
+    }
+}
+
+impl PayloadSpec {
+    fn into_packet_type(self) -> Result<PacketType, NetworkError> {
+        let pack_type = match self {
+            PayloadSpec::MsgFragment {
+                fragment_index,
+                total_n_fragments,
+                data,
+                length,
+            } => {
+                if data.len() > FRAGMENT_DSIZE {
+                    return Err(NetworkError::ValidationError(format!(
+                        "Fragment data is {} bytes, the maximum is {}",
+                        data.len(),
+                        FRAGMENT_DSIZE
+                    )));
+                }
+                let mut buffer = [0; FRAGMENT_DSIZE];
+                buffer[..data.len()].copy_from_slice(&data);
+                PacketType::MsgFragment(Fragment {
+                    fragment_index,
+                    total_n_fragments,
+                    length: length.unwrap_or(data.len() as u8),
+                    data: buffer,
+                })
+            }
+            PayloadSpec::Ack { fragment_index } => PacketType::Ack(Ack { fragment_index }),
+            PayloadSpec::Nack {
+                fragment_index,
+                nack_type,
+            } => PacketType::Nack(Nack {
+                fragment_index,
+                nack_type,
+            }),
+            PayloadSpec::FloodRequest {
+                flood_id,
+                initiator_id,
+                initiator_type,
+                path_trace,
+            } => PacketType::FloodRequest(FloodRequest {
+                flood_id,
+                initiator_id,
+                path_trace: path_trace.unwrap_or_else(|| vec![(initiator_id, initiator_type)]),
+            }),
+            PayloadSpec::FloodResponse {
+                flood_id,
+                path_trace,
+            } => PacketType::FloodResponse(FloodResponse {
+                flood_id,
+                path_trace,
+            }),
+        };
+        Ok(pack_type)
+    }
+}
+
+/// Sends `packet` on the inbound channel of `node_id`, as if a neighbor had sent it.
+pub fn inject_packet(
+    state: &SimulationState,
+    node_id: NodeId,
+    packet: Packet,
+) -> Result<(), NetworkError> {
+    let (sender, _) = state
+        .get_inter_node_channels()
+        .get(&node_id)
+        .ok_or(NetworkError::ChannelNotFound(node_id))?;
+
+    sender.send(packet).map_err(|err| {
+        NetworkError::SendError(format!(
+            "Failed to send packet to node {}: {:?}",
+            node_id, err
+        ))
+    })
+}
@@ -0,0 +1,164 @@
+use crate::error::NetworkError;
+use crate::simulation::controller::controller_commands::{
+    send_add_sender_command, send_crash_command, send_remove_sender_command,
+};
+use crate::simulation::state::SimulationState;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use wg_2024::network::NodeId;
+
+/// A change made to the running network by the simulation controller, recorded so that it
+/// can be undone if a later step of the same operation fails.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum SideEffect {
+    /// `node_id` was told to add `neighbor_id` to its senders
+    AddedSender {
+        node_id: NodeId,
+        neighbor_id: NodeId,
+    },
+    /// `node_id` was told to remove `neighbor_id` from its senders
+    RemovedSender {
+        node_id: NodeId,
+        neighbor_id: NodeId,
+    },
+    /// A new drone was created. Its thread may not have been started.
+    SpawnedDrone { drone_id: NodeId },
+    /// The drone was told to crash. A crash cannot be undone.
+    CrashedDrone { drone_id: NodeId },
+}
+
+impl SideEffect {
+    /// Sends the commands that undo the effect.
+    pub fn compensate(&self, state: &mut SimulationState) -> Result<(), NetworkError> {
+        match *self {
+            SideEffect::AddedSender {
+                node_id,
+                neighbor_id,
+            } => send_remove_sender_command(state, node_id, neighbor_id),
+            SideEffect::RemovedSender {
+                node_id,
+                neighbor_id,
+            } => send_add_sender_command(state, node_id, neighbor_id),
+            SideEffect::SpawnedDrone { drone_id } => despawn_drone(state, drone_id),
+            SideEffect::CrashedDrone { drone_id } => Err(NetworkError::InvalidOperation(format!(
+                "Drone {} already crashed",
+                drone_id
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for SideEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SideEffect::AddedSender {
+                node_id,
+                neighbor_id,
+            } => write!(f, "{} added sender {}", node_id, neighbor_id),
+            SideEffect::RemovedSender {
+                node_id,
+                neighbor_id,
+            } => write!(f, "{} removed sender {}", node_id, neighbor_id),
+            SideEffect::SpawnedDrone { drone_id } => write!(f, "drone {} spawned", drone_id),
+            SideEffect::CrashedDrone { drone_id } => write!(f, "drone {} crashed", drone_id),
+        }
+    }
+}
+
+/// Formats `effects` as a comma separated list, for the error messages.
+pub fn describe(effects: &[SideEffect]) -> String {
+    if effects.is_empty() {
+        return "none".to_string();
+    }
+    effects
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Tells `node_id` to add `neighbor_id` to its senders, and records it in `effects`.
+pub fn add_sender(
+    state: &SimulationState,
+    node_id: NodeId,
+    neighbor_id: NodeId,
+    effects: &mut Vec<SideEffect>,
+) -> Result<(), NetworkError> {
+    send_add_sender_command(state, node_id, neighbor_id)?;
+    effects.push(SideEffect::AddedSender {
+        node_id,
+        neighbor_id,
+    });
+    Ok(())
+}
+
+/// Tells `node_id` to remove `neighbor_id` from its senders, and records it in `effects`.
+pub fn remove_sender(
+    state: &SimulationState,
+    node_id: NodeId,
+    neighbor_id: NodeId,
+    effects: &mut Vec<SideEffect>,
+) -> Result<(), NetworkError> {
+    send_remove_sender_command(state, node_id, neighbor_id)?;
+    effects.push(SideEffect::RemovedSender {
+        node_id,
+        neighbor_id,
+    });
+    Ok(())
+}
+
+/// Undoes `effects`, most recent first, after `error` interrupted the operation that made
+/// them. Returns the error to report: `error` itself if there was nothing to undo, otherwise
+/// `NetworkError::RolledBack` with the effects that were and were not undone.
+///
+/// Undoing is best effort: an effect that cannot be undone is logged and skipped.
+pub fn rollback(
+    state: &mut SimulationState,
+    effects: &[SideEffect],
+    error: NetworkError,
+) -> NetworkError {
+    if effects.is_empty() {
+        return error;
+    }
+    error!("{}, rolling back: {}", error, describe(effects));
+
+    let mut rolled_back = Vec::new();
+    let mut not_rolled_back = Vec::new();
+    for effect in effects.iter().rev() {
+        match effect.compensate(state) {
+            Ok(()) => {
+                info!("Rolled back {}", effect);
+                rolled_back.push(effect.clone());
+            }
+            Err(err) => {
+                error!("Failed to roll back {}: {}", effect, err);
+                not_rolled_back.push(effect.clone());
+            }
+        }
+    }
+    NetworkError::RolledBack {
+        error: Box::new(error),
+        rolled_back,
+        not_rolled_back,
+    }
+}
+
+/// Stops a drone started by the controller and forgets its channels and its metrics. The
+/// drone may have failed to start, leaving only part of them.
+fn despawn_drone(state: &mut SimulationState, drone_id: NodeId) -> Result<(), NetworkError> {
+    // Only a running drone can receive the command
+    if state.get_nodes_threads().contains_key(&drone_id) {
+        send_crash_command(state, drone_id)?;
+    }
+    state.get_inter_node_channels_mut().remove(&drone_id);
+    state.get_drone_controller_channels_mut().remove(&drone_id);
+    state.get_metrics_mut().remove_node(drone_id);
+    if let Some(handle) = state.get_nodes_threads_mut().remove(&drone_id) {
+        handle
+            .join()
+            .map_err(|_| NetworkError::ThreadJoinError(drone_id.to_string()))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,491 @@
+use crate::error::NetworkError;
+use crate::simulation::configs::configs_handler::{
+    add_edge_to_config, insert_node_into_config, next_config_node_id, remove_edge_from_config,
+    remove_node_from_config,
+};
+use crate::simulation::controller::controller_commands::send_crash_command;
+use crate::simulation::controller::side_effects::{
+    add_sender, remove_sender, rollback, SideEffect,
+};
+use crate::simulation::state::SimulationState;
+use crate::simulation::topology::edits::TopologyEdit;
+use crate::simulation::topology::validations::validate_graph;
+use crate::simulation::topology::{GraphState, NodeMetadata};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use wg_2024::network::NodeId;
+use wg_2024::packet::NodeType;
+
+/// The outcome of a committed transaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionReport {
+    /// The drones added by the transaction
+    pub added_drones: Vec<NodeId>,
+    /// Every change made to the running network
+    pub side_effects: Vec<SideEffect>,
+}
+
+/// Applies `edits` to the running network as a whole.
+///
+/// Only the final graph is validated, so the intermediate steps may be invalid. The commands
+/// are then sent from the difference between the current and the final graph: new drones
+/// first, then the new links, the removed links and finally the crashes, which cannot be
+/// undone. If a command cannot be sent, the commands already sent are undone and the graph
+/// is left unchanged, except for the drones already crashed: they are kept out of the graph,
+/// and their neighbors are not told to add them back.
+pub fn apply_transaction(
+    state: &mut SimulationState,
+    edits: &[TopologyEdit],
+) -> Result<TransactionReport, NetworkError> {
+    let old_graph = state.get_graph().clone();
+    let new_graph = final_graph(&old_graph, edits)?;
+    validate_graph(&new_graph, state.get_strict_mode())?;
+
+    let crashed: Vec<NodeId> = sorted_nodes(&old_graph)
+        .into_iter()
+        .filter(|id| new_graph.get_node_type(*id).is_none())
+        .collect();
+    // A drone added and crashed by the transaction is never started
+    let added_drones: Vec<NodeId> = sorted_nodes(&new_graph)
+        .into_iter()
+        .filter(|id| old_graph.get_node_type(*id).is_none())
+        .collect();
+    // Crashes cannot be undone: make sure they can all be sent before changing anything
+    for &drone_id in &crashed {
+        if !state
+            .get_drone_controller_channels()
+            .contains_key(&drone_id)
+        {
+            return Err(NetworkError::ChannelNotFound(drone_id));
+        }
+    }
+
+    // The commands look the nodes up in the graph, which must know the new drones
+    let commands = plan_commands((&old_graph, &new_graph), &added_drones, &crashed);
+    state.set_graph(new_graph.clone());
+    let mut effects = Vec::new();
+    if let Err(err) = send_commands(state, &new_graph, &commands, &mut effects) {
+        return Err(abort(state, old_graph, &effects, err));
+    }
+
+    forget_crashed_drones(state, &crashed);
+    Ok(TransactionReport {
+        added_drones,
+        side_effects: effects,
+    })
+}
+
+/// Applies `edits` to the loaded configuration as a whole. Only the final configuration is
+/// validated: on error, the configuration is left unchanged.
+pub fn apply_config_transaction(
+    state: &mut SimulationState,
+    edits: &[TopologyEdit],
+) -> Result<TransactionReport, NetworkError> {
+    let snapshot = state
+        .get_config()
+        .cloned()
+        .ok_or(NetworkError::NoConfigLoaded)?;
+    // The configuration functions do not check that the nodes and the links exist
+    let mut graph = state.get_topology().into_owned();
+    let result = edits
+        .iter()
+        .try_for_each(|edit| apply_config_edit(state, &mut graph, edit))
+        .and_then(|()| {
+            let graph = state.get_topology();
+            validate_graph(&graph, state.get_strict_mode())
+        });
+    if let Err(err) = result {
+        if let Some(config) = state.get_config_mut() {
+            *config = snapshot;
+        }
+        return Err(err);
+    }
+
+    let added_drones = state
+        .get_config()
+        .map(|config| {
+            config
+                .drone
+                .iter()
+                .map(|drone| drone.id)
+                .filter(|id| !snapshot.drone.iter().any(|drone| drone.id == *id))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(TransactionReport {
+        added_drones,
+        side_effects: Vec::new(),
+    })
+}
+
+/// Checks `edit` on `graph`, the topology of the configuration, then applies it to both.
+fn apply_config_edit(
+    state: &mut SimulationState,
+    graph: &mut GraphState,
+    edit: &TopologyEdit,
+) -> Result<(), NetworkError> {
+    match edit {
+        TopologyEdit::CrashDrone { drone_id } => {
+            graph.crash_drone(*drone_id)?;
+            remove_node_from_config(state, *drone_id)
+        }
+        TopologyEdit::RemoveEdge { node1_id, node2_id } => {
+            graph.remove_edge(*node1_id, *node2_id)?;
+            remove_edge_from_config(state, *node1_id, *node2_id)
+        }
+        TopologyEdit::AddEdge { node1_id, node2_id } => {
+            graph.add_edge(*node1_id, *node2_id)?;
+            add_edge_to_config(state, *node1_id, *node2_id)
+        }
+        TopologyEdit::AddDrone { neighbors, pdr } => {
+            // The same ID on both sides, so that the later edits can refer to the drone
+            let node_id = next_config_node_id(state)?;
+            graph.add_drone(node_id, neighbors.clone(), *pdr)?;
+            insert_node_into_config(
+                state,
+                node_id,
+                NodeType::Drone,
+                neighbors.clone(),
+                Some(*pdr),
+            )
+        }
+    }
+}
+
+/// Undoes the commands sent by a transaction interrupted by `error` and restores
+/// `old_graph`, without the drones that were already crashed.
+fn abort(
+    state: &mut SimulationState,
+    mut old_graph: GraphState,
+    effects: &[SideEffect],
+    error: NetworkError,
+) -> NetworkError {
+    let (crashed, kept, undone) = split_crashes(effects);
+
+    // The new drones must still be in the graph to undo their commands
+    let error = rollback(state, &undone, error);
+    for &drone_id in &crashed {
+        if let Err(err) = old_graph.crash_drone(drone_id) {
+            warn!("Failed to remove the crashed drone {}: {}", drone_id, err);
+        }
+    }
+    state.set_graph(old_graph);
+    forget_crashed_drones(state, &crashed);
+
+    if kept.is_empty() {
+        return error;
+    }
+    match error {
+        NetworkError::RolledBack {
+            error,
+            rolled_back,
+            mut not_rolled_back,
+        } => {
+            not_rolled_back.extend(kept);
+            NetworkError::RolledBack {
+                error,
+                rolled_back,
+                not_rolled_back,
+            }
+        }
+        error => NetworkError::RolledBack {
+            error: Box::new(error),
+            rolled_back: Vec::new(),
+            not_rolled_back: kept,
+        },
+    }
+}
+
+/// Forgets the controller channels of the crashed drones and waits for their threads.
+fn forget_crashed_drones(state: &mut SimulationState, crashed: &[NodeId]) {
+    for &drone_id in crashed {
+        state.get_drone_controller_channels_mut().remove(&drone_id);
+        if let Some(handle) = state.get_nodes_threads_mut().remove(&drone_id) {
+            if handle.join().is_err() {
+                warn!(
+                    "Failed to join the thread of the crashed drone {}",
+                    drone_id
+                );
+            }
+        }
+    }
+}
+
+/// Applies `edits` to a copy of `graph`, without validating the result.
+fn final_graph(graph: &GraphState, edits: &[TopologyEdit]) -> Result<GraphState, NetworkError> {
+    let mut graph = graph.clone();
+    let mut crashed_ids = BTreeSet::new();
+    for edit in edits {
+        if let TopologyEdit::CrashDrone { drone_id } = edit {
+            crashed_ids.insert(*drone_id);
+        }
+        if let Some(drone_id) = edit.apply(&mut graph)? {
+            // The ID of a drone crashed earlier in the transaction may be given again
+            if crashed_ids.contains(&drone_id) {
+                return Err(NetworkError::InvalidOperation(format!(
+                    "Drone {} cannot be crashed and added in the same transaction",
+                    drone_id
+                )));
+            }
+        }
+    }
+    Ok(graph)
+}
+
+/// Returns the commands turning `old_graph` into `new_graph`, as the effects they have, in
+/// sending order.
+fn plan_commands(
+    (old_graph, new_graph): (&GraphState, &GraphState),
+    added_drones: &[NodeId],
+    crashed: &[NodeId],
+) -> Vec<SideEffect> {
+    // New drones start without neighbors: their links are added like the others
+    let mut commands: Vec<SideEffect> = added_drones
+        .iter()
+        .map(|&drone_id| SideEffect::SpawnedDrone { drone_id })
+        .collect();
+
+    for &(node_id, neighbor_id) in links(new_graph).difference(&links(old_graph)) {
+        for (node_id, neighbor_id) in [(node_id, neighbor_id), (neighbor_id, node_id)] {
+            commands.push(SideEffect::AddedSender {
+                node_id,
+                neighbor_id,
+            });
+        }
+    }
+
+    for &(node_id, neighbor_id) in links(old_graph).difference(&links(new_graph)) {
+        for (node_id, neighbor_id) in [(node_id, neighbor_id), (neighbor_id, node_id)] {
+            // The crashed drones do not need to forget their neighbors
+            if !crashed.contains(&node_id) {
+                commands.push(SideEffect::RemovedSender {
+                    node_id,
+                    neighbor_id,
+                });
+            }
+        }
+    }
+
+    commands.extend(
+        crashed
+            .iter()
+            .map(|&drone_id| SideEffect::CrashedDrone { drone_id }),
+    );
+    commands
+}
+
+/// Sends `commands`, recording in `effects` the ones that took effect.
+fn send_commands(
+    state: &mut SimulationState,
+    new_graph: &GraphState,
+    commands: &[SideEffect],
+    effects: &mut Vec<SideEffect>,
+) -> Result<(), NetworkError> {
+    for command in commands {
+        match *command {
+            SideEffect::SpawnedDrone { drone_id } => {
+                let pdr = match new_graph.get_node_type(drone_id) {
+                    Some(NodeMetadata::Drone(drone)) => (drone.get_pdr() * 100.0).round() as u32,
+                    _ => return Err(NetworkError::NodeIsNotDrone(drone_id)),
+                };
+                // Recorded first: a drone that fails to start still leaves channels and
+                // metrics behind
+                effects.push(command.clone());
+                crate::simulation::initializer::network_initializer::create_new_drone(
+                    state,
+                    drone_id,
+                    vec![],
+                    pdr,
+                )?;
+            }
+            SideEffect::AddedSender {
+                node_id,
+                neighbor_id,
+            } => add_sender(state, node_id, neighbor_id, effects)?,
+            SideEffect::RemovedSender {
+                node_id,
+                neighbor_id,
+            } => remove_sender(state, node_id, neighbor_id, effects)?,
+            SideEffect::CrashedDrone { drone_id } => {
+                send_crash_command(state, drone_id)?;
+                state.get_inter_node_channels_mut().remove(&drone_id);
+                effects.push(command.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Splits the effects of an interrupted transaction into the drones it crashed, the effects
+/// that must be kept because of these crashes, and the effects to undo.
+fn split_crashes(effects: &[SideEffect]) -> (Vec<NodeId>, Vec<SideEffect>, Vec<SideEffect>) {
+    let crashed: Vec<NodeId> = effects
+        .iter()
+        .filter_map(|effect| match effect {
+            SideEffect::CrashedDrone { drone_id } => Some(*drone_id),
+            _ => None,
+        })
+        .collect();
+    // The neighbors of a crashed drone must not add it back to their senders
+    let (kept, undone) = effects.iter().cloned().partition(|effect| match effect {
+        SideEffect::CrashedDrone { .. } => true,
+        SideEffect::RemovedSender { neighbor_id, .. } => crashed.contains(neighbor_id),
+        _ => false,
+    });
+    (crashed, kept, undone)
+}
+
+fn sorted_nodes(graph: &GraphState) -> Vec<NodeId> {
+    let mut nodes = graph.get_nodes();
+    nodes.sort_unstable();
+    nodes
+}
+
+/// The undirected links of `graph`, as `(lowest ID, highest ID)`.
+fn links(graph: &GraphState) -> BTreeSet<(NodeId, NodeId)> {
+    graph
+        .adjacency
+        .iter()
+        .flat_map(|(&node, neighbors)| {
+            neighbors
+                .iter()
+                .map(move |&neighbor| (node.min(neighbor), node.max(neighbor)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::topology::test_graph;
+
+    /// Client 1 and server 2, linked by the drones 10, 11 and 12:
+    ///
+    /// ```text
+    /// 1 -- 10 -- 11 -- 2
+    ///        \        /
+    ///         12 ----
+    /// ```
+    fn graph() -> GraphState {
+        test_graph(
+            &[(10, &[1, 11, 12]), (11, &[10, 2]), (12, &[10, 2])],
+            &[(1, &[10])],
+            &[(2, &[11, 12])],
+        )
+    }
+
+    #[test]
+    fn links_are_undirected_and_sorted() {
+        assert_eq!(
+            links(&graph()).into_iter().collect::<Vec<_>>(),
+            [(1, 10), (2, 11), (2, 12), (10, 11), (10, 12)]
+        );
+    }
+
+    #[test]
+    fn crashed_id_cannot_be_added_again() {
+        // 4 nodes: the next ID is 5
+        let graph = test_graph(
+            &[(2, &[1, 3, 4]), (4, &[2, 3])],
+            &[(1, &[2])],
+            &[(3, &[2, 4])],
+        );
+        let add = TopologyEdit::AddDrone {
+            neighbors: vec![2],
+            pdr: 0,
+        };
+        let new_graph = final_graph(&graph, std::slice::from_ref(&add)).unwrap();
+        assert!(new_graph.get_node_type(5).is_some());
+
+        // Once drone 4 is crashed, the next ID is 4 again
+        let crash = TopologyEdit::CrashDrone { drone_id: 4 };
+        assert!(matches!(
+            final_graph(&graph, &[crash, add]),
+            Err(NetworkError::InvalidOperation(_))
+        ));
+    }
+
+    #[test]
+    fn commands_spawn_then_link_then_unlink_then_crash() {
+        let old_graph = graph();
+        let edits = [
+            TopologyEdit::CrashDrone { drone_id: 12 },
+            TopologyEdit::RemoveEdge {
+                node1_id: 10,
+                node2_id: 11,
+            },
+            TopologyEdit::AddDrone {
+                neighbors: vec![10, 2],
+                pdr: 0,
+            },
+        ];
+        let new_graph = final_graph(&old_graph, &edits).unwrap();
+        // 4 nodes once drone 12 is crashed: the new drone is 5
+        assert!(new_graph.get_node_type(5).is_some());
+
+        let added = |node_id, neighbor_id| SideEffect::AddedSender {
+            node_id,
+            neighbor_id,
+        };
+        let removed = |node_id, neighbor_id| SideEffect::RemovedSender {
+            node_id,
+            neighbor_id,
+        };
+        assert_eq!(
+            plan_commands((&old_graph, &new_graph), &[5], &[12]),
+            [
+                SideEffect::SpawnedDrone { drone_id: 5 },
+                added(2, 5),
+                added(5, 2),
+                added(5, 10),
+                added(10, 5),
+                // The crashed drone does not forget its neighbors
+                removed(2, 12),
+                removed(10, 11),
+                removed(11, 10),
+                removed(10, 12),
+                SideEffect::CrashedDrone { drone_id: 12 },
+            ]
+        );
+    }
+
+    #[test]
+    fn unchanged_graph_needs_no_command() {
+        let graph = graph();
+        assert!(plan_commands((&graph, &graph), &[], &[]).is_empty());
+    }
+
+    #[test]
+    fn effects_of_sent_crashes_are_kept() {
+        let effects = [
+            SideEffect::SpawnedDrone { drone_id: 5 },
+            SideEffect::AddedSender {
+                node_id: 5,
+                neighbor_id: 10,
+            },
+            SideEffect::RemovedSender {
+                node_id: 2,
+                neighbor_id: 12,
+            },
+            SideEffect::RemovedSender {
+                node_id: 10,
+                neighbor_id: 11,
+            },
+            SideEffect::CrashedDrone { drone_id: 12 },
+        ];
+        let (crashed, kept, undone) = split_crashes(&effects);
+        assert_eq!(crashed, [12]);
+        assert_eq!(kept, [effects[2].clone(), effects[4].clone()]);
+        assert_eq!(
+            undone,
+            [effects[0].clone(), effects[1].clone(), effects[3].clone()]
+        );
+
+        // Before any crash, everything is undone
+        let (crashed, kept, undone) = split_crashes(&effects[..4]);
+        assert!(crashed.is_empty());
+        assert!(kept.is_empty());
+        assert_eq!(undone, effects[..4]);
+    }
+}
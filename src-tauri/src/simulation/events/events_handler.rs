@@ -0,0 +1,340 @@
+use crate::simulation::events::{
+    EventLog, EventPage, EventQuery, EventType, HostMessageView, LoggedEvent, NodeKind, PacketView,
+    PayloadView, DEFAULT_PAGE_SIZE, MAX_LOGGED_EVENTS,
+};
+use crate::simulation::listener::shortcuts::ShortcutFailure;
+use crate::simulation::metrics::{NackTypeLabel, PacketTypeLabel};
+use crate::utils::ControllerEvent;
+use common_utils::{HostEvent, PacketHeader};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{NackType, Packet, PacketType};
+
+impl EventLog {
+    /// Stamps the events received from now on with `run_id`.
+    pub fn start_run(&mut self, run_id: u64) {
+        self.run_id = run_id;
+    }
+
+    /// Appends an event received at the UNIX time `now_ms` and at the run-relative time
+    /// `sim_time_ms`, assigning it the next sequence number.
+    pub fn push(&mut self, event: &ControllerEvent, now_ms: u64, sim_time_ms: u64) {
+        self.next_seq += 1;
+        self.append(LoggedEvent::new(
+            self.next_seq,
+            self.run_id,
+            (now_ms, sim_time_ms),
+            event,
+        ));
+    }
+
+    /// Appends the failure of the delivery of a packet sent through the simulation controller
+    /// by the node `node_id`.
+    pub fn push_shortcut_failure(
+        &mut self,
+        (node_id, node_type): (NodeId, NodeKind),
+        packet: &Packet,
+        failure: ShortcutFailure,
+        (now_ms, sim_time_ms): (u64, u64),
+    ) {
+        self.next_seq += 1;
+        self.append(LoggedEvent {
+            seq: self.next_seq,
+            run_id: self.run_id,
+            timestamp: now_ms,
+            sim_time_ms,
+            event_type: EventType::ShortcutFailed,
+            node: node_id,
+            node_type,
+            packet: Some(PacketView::from(packet)),
+            host_message: None,
+            shortcut_failure: Some(failure),
+        });
+    }
+
+    /// Appends a packet delivered to the node `node_id`, as seen by the sniffer.
+    pub fn push_delivery(
+        &mut self,
+        (node_id, node_type): (NodeId, NodeKind),
+        packet: &Packet,
+        (timestamp, sim_time_ms): (u64, u64),
+    ) {
+        self.next_seq += 1;
+        self.append(LoggedEvent {
+            seq: self.next_seq,
+            run_id: self.run_id,
+            timestamp,
+            sim_time_ms,
+            event_type: EventType::PacketDelivered,
+            node: node_id,
+            node_type,
+            packet: Some(PacketView::from(packet)),
+            host_message: None,
+            shortcut_failure: None,
+        });
+    }
+
+    fn append(&mut self, event: LoggedEvent) {
+        self.events.push_back(event);
+        if self.events.len() > MAX_LOGGED_EVENTS {
+            self.events.pop_front();
+        }
+    }
+
+    /// Returns the latest `max_events` events with a sequence number greater than `last_seq`.
+    pub fn since(&self, last_seq: u64, max_events: usize) -> Vec<LoggedEvent> {
+        let first = self.events.partition_point(|e| e.seq <= last_seq);
+        let first = first.max(self.events.len().saturating_sub(max_events));
+        self.events.range(first..).cloned().collect()
+    }
+
+    /// Returns the page of the events matching `query`, oldest first, with the counts of
+    /// every matching event.
+    pub fn query(&self, query: &EventQuery) -> EventPage {
+        let text = query.text.as_ref().map(|text| text.to_lowercase());
+        let offset = query.offset.unwrap_or(0);
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+
+        let mut page = EventPage {
+            total: 0,
+            counts_by_event_type: HashMap::new(),
+            counts_by_packet_type: HashMap::new(),
+            offset,
+            events: Vec::new(),
+        };
+        for event in self
+            .events
+            .iter()
+            .filter(|e| e.matches(query, text.as_deref()))
+        {
+            if page.total >= offset && page.events.len() < limit {
+                page.events.push(event.clone());
+            }
+            page.total += 1;
+            *page
+                .counts_by_event_type
+                .entry(event.event_type)
+                .or_insert(0) += 1;
+            if let Some(packet) = &event.packet {
+                *page
+                    .counts_by_packet_type
+                    .entry(packet.payload.packet_type())
+                    .or_insert(0) += 1;
+            }
+        }
+        page
+    }
+}
+
+impl LoggedEvent {
+    /// Builds the view of `event`, received at `(timestamp, sim_time_ms)`.
+    pub fn new(
+        seq: u64,
+        run_id: u64,
+        (timestamp, sim_time_ms): (u64, u64),
+        event: &ControllerEvent,
+    ) -> Self {
+        let (event_type, node, node_type, packet, host_message) = match event {
+            ControllerEvent::Drone { node_id, event } => {
+                let (event_type, packet) = match event {
+                    DroneEvent::PacketSent(packet) => (EventType::PacketSent, packet),
+                    DroneEvent::PacketDropped(packet) => (EventType::PacketDropped, packet),
+                    DroneEvent::ControllerShortcut(packet) => {
+                        (EventType::ControllerShortcut, packet)
+                    }
+                };
+                let packet = Some(PacketView::from(packet));
+                (event_type, *node_id, NodeKind::Drone, packet, None)
+            }
+            ControllerEvent::Host { node_id, event } => match event {
+                HostEvent::HostMessageSent(destination, message, duration) => {
+                    let host_message = HostMessageView::new(*destination, message, *duration);
+                    let event_type = EventType::HostMessageSent;
+                    (
+                        event_type,
+                        *node_id,
+                        NodeKind::Host,
+                        None,
+                        Some(host_message),
+                    )
+                }
+                HostEvent::PacketSent(packet_header) => {
+                    let packet = Some(PacketView::from(packet_header));
+                    (
+                        EventType::PacketSent,
+                        *node_id,
+                        NodeKind::Host,
+                        packet,
+                        None,
+                    )
+                }
+                HostEvent::ControllerShortcut(packet) => {
+                    let packet = Some(PacketView::from(packet));
+                    let event_type = EventType::ControllerShortcut;
+                    (event_type, *node_id, NodeKind::Host, packet, None)
+                }
+            },
+        };
+
+        LoggedEvent {
+            seq,
+            run_id,
+            timestamp,
+            sim_time_ms,
+            event_type,
+            node,
+            node_type,
+            packet,
+            host_message,
+            shortcut_failure: None,
+        }
+    }
+}
+
+impl LoggedEvent {
+    fn matches(&self, query: &EventQuery, text: Option<&str>) -> bool {
+        let packet = self.packet.as_ref();
+        query
+            .nodes
+            .as_ref()
+            .is_none_or(|nodes| nodes.contains(&self.node))
+            && query.node_type.is_none_or(|kind| kind == self.node_type)
+            && query
+                .event_types
+                .as_ref()
+                .is_none_or(|types| types.contains(&self.event_type))
+            && query.packet_types.as_ref().is_none_or(|types| {
+                packet.is_some_and(|p| types.contains(&p.payload.packet_type()))
+            })
+            && query
+                .session_id
+                .is_none_or(|id| packet.is_some_and(|p| p.session_id == Some(id)))
+            && query.run_id.is_none_or(|id| id == self.run_id)
+            && query.from_ms.is_none_or(|from| self.timestamp >= from)
+            && query.to_ms.is_none_or(|to| self.timestamp <= to)
+            && query.source.is_none_or(|source| {
+                packet.is_some_and(|p| p.source == Some(source))
+                    || (self.host_message.is_some() && self.node == source)
+            })
+            && query.destination.is_none_or(|destination| {
+                packet.is_some_and(|p| p.destination == Some(destination))
+                    || self
+                        .host_message
+                        .as_ref()
+                        .is_some_and(|m| m.destination == destination)
+            })
+            && query
+                .hop
+                .is_none_or(|hop| packet.is_some_and(|p| p.hops.contains(&hop)))
+            && text.is_none_or(|text| {
+                self.host_message
+                    .as_ref()
+                    .is_some_and(|m| m.content.to_string().to_lowercase().contains(text))
+            })
+    }
+}
+
+impl HostMessageView {
+    fn new(destination: NodeId, message: &impl Serialize, duration: Duration) -> Self {
+        let content = serde_json::to_value(message).unwrap_or(Value::Null);
+        // Enums are serialized as `"Variant"` or `{ "Variant": ... }`
+        let kind = match &content {
+            Value::String(variant) => Some(variant.clone()),
+            Value::Object(fields) if fields.len() == 1 => fields.keys().next().cloned(),
+            _ => None,
+        };
+        HostMessageView {
+            destination,
+            latency_ms: duration.as_millis() as u64,
+            kind,
+            content,
+        }
+    }
+}
+
+impl PacketView {
+    fn new(
+        routing_header: &SourceRoutingHeader,
+        session_id: Option<u64>,
+        payload: PayloadView,
+    ) -> Self {
+        PacketView {
+            source: routing_header.source(),
+            destination: routing_header.destination(),
+            hops: routing_header.hops.clone(),
+            hop_index: routing_header.hop_index,
+            session_id,
+            payload,
+        }
+    }
+}
+
+impl From<&Packet> for PacketView {
+    fn from(packet: &Packet) -> Self {
+        PacketView::new(
+            &packet.routing_header,
+            Some(packet.session_id),
+            PayloadView::from(&packet.pack_type),
+        )
+    }
+}
+
+impl From<&PacketHeader> for PacketView {
+    fn from(header: &PacketHeader) -> Self {
+        let payload = PayloadView::HeaderOnly {
+            packet_type: PacketTypeLabel::from(&header.pack_type),
+        };
+        PacketView::new(&header.routing_header, None, payload)
+    }
+}
+
+impl PayloadView {
+    pub fn packet_type(&self) -> PacketTypeLabel {
+        match self {
+            PayloadView::MsgFragment { .. } => PacketTypeLabel::MsgFragment,
+            PayloadView::Ack { .. } => PacketTypeLabel::Ack,
+            PayloadView::Nack { .. } => PacketTypeLabel::Nack,
+            PayloadView::FloodRequest { .. } => PacketTypeLabel::FloodRequest,
+            PayloadView::FloodResponse { .. } => PacketTypeLabel::FloodResponse,
+            PayloadView::HeaderOnly { packet_type } => *packet_type,
+        }
+    }
+}
+
+impl From<&PacketType> for PayloadView {
+    fn from(pack_type: &PacketType) -> Self {
+        match pack_type {
+            PacketType::MsgFragment(fragment) => PayloadView::MsgFragment {
+                fragment_index: fragment.fragment_index,
+                total_n_fragments: fragment.total_n_fragments,
+                length: fragment.length,
+            },
+            PacketType::Ack(ack) => PayloadView::Ack {
+                fragment_index: ack.fragment_index,
+            },
+            PacketType::Nack(nack) => PayloadView::Nack {
+                fragment_index: nack.fragment_index,
+                nack_type: NackTypeLabel::from(&nack.nack_type),
+                error_node: match nack.nack_type {
+                    NackType::ErrorInRouting(node) | NackType::UnexpectedRecipient(node) => {
+                        Some(node)
+                    }
+                    NackType::DestinationIsDrone | NackType::Dropped => None,
+                },
+            },
+            PacketType::FloodRequest(request) => PayloadView::FloodRequest {
+                flood_id: request.flood_id,
+                initiator_id: request.initiator_id,
+                path_trace: request.path_trace.clone(),
+            },
+            PacketType::FloodResponse(response) => PayloadView::FloodResponse {
+                flood_id: response.flood_id,
+                path_trace: response.path_trace.clone(),
+            },
+        }
+    }
+}
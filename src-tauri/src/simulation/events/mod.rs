@@ -0,0 +1,153 @@
+pub mod events_handler;
+
+use crate::simulation::listener::shortcuts::ShortcutFailure;
+use crate::simulation::metrics::{NackTypeLabel, PacketTypeLabel};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use wg_2024::network::NodeId;
+use wg_2024::packet::NodeType;
+
+/// Maximum number of events kept in the log. The oldest event is evicted first.
+const MAX_LOGGED_EVENTS: usize = 100_000;
+
+/// Number of events returned by a query when no limit is given.
+const DEFAULT_PAGE_SIZE: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EventType {
+    PacketSent,
+    PacketDropped,
+    ControllerShortcut,
+    HostMessageSent,
+    /// A packet sent through the simulation controller was rejected or lost
+    ShortcutFailed,
+    /// A packet was delivered on the inbound channel of a node, as seen by the sniffer
+    PacketDelivered,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodeKind {
+    Drone,
+    Host,
+}
+
+/// An event reported to the simulation controller, as exposed to the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedEvent {
+    /// Sequence number of the event, monotonic across runs
+    pub seq: u64,
+    /// The run during which the event was received
+    pub run_id: u64,
+    /// UNIX timestamp in milliseconds at which the listener received the event
+    pub timestamp: u64,
+    /// Milliseconds elapsed since the start of the run
+    pub sim_time_ms: u64,
+    pub event_type: EventType,
+    pub node: NodeId,
+    pub node_type: NodeKind,
+    /// The packet carried by the event, for every event but `HostMessageSent`
+    pub packet: Option<PacketView>,
+    /// The message carried by the event, for `HostMessageSent`
+    pub host_message: Option<HostMessageView>,
+    /// Why the shortcut was not delivered, for `ShortcutFailed`
+    pub shortcut_failure: Option<ShortcutFailure>,
+}
+
+/// A serializable view of a packet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PacketView {
+    pub source: Option<NodeId>,
+    pub destination: Option<NodeId>,
+    pub hops: Vec<NodeId>,
+    pub hop_index: usize,
+    /// Not available for the packets reported by hosts, which only report the header
+    pub session_id: Option<u64>,
+    pub payload: PayloadView,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum PayloadView {
+    MsgFragment {
+        fragment_index: u64,
+        total_n_fragments: u64,
+        length: u8,
+    },
+    Ack {
+        fragment_index: u64,
+    },
+    Nack {
+        fragment_index: u64,
+        nack_type: NackTypeLabel,
+        /// The node referenced by `ErrorInRouting` and `UnexpectedRecipient`
+        error_node: Option<NodeId>,
+    },
+    FloodRequest {
+        flood_id: u64,
+        initiator_id: NodeId,
+        path_trace: Vec<(NodeId, NodeType)>,
+    },
+    FloodResponse {
+        flood_id: u64,
+        path_trace: Vec<(NodeId, NodeType)>,
+    },
+    /// Only the packet type is known: hosts report the header of the packets they send
+    HeaderOnly {
+        packet_type: PacketTypeLabel,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostMessageView {
+    pub destination: NodeId,
+    /// Time taken to deliver the message, in milliseconds
+    pub latency_ms: u64,
+    /// The variant of the message, e.g. `ChatMessage`
+    pub kind: Option<String>,
+    /// The message, as serialized by the host
+    pub content: serde_json::Value,
+}
+
+/// The events received by the listener, oldest first. The log is kept across runs, so
+/// that sequence numbers never restart.
+#[derive(Debug, Default)]
+pub struct EventLog {
+    events: VecDeque<LoggedEvent>,
+    next_seq: u64,
+    run_id: u64,
+}
+
+/// Filters accepted by `query_events`. Unset fields match every event.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventQuery {
+    /// The node that reported the event
+    pub nodes: Option<Vec<NodeId>>,
+    pub node_type: Option<NodeKind>,
+    pub event_types: Option<Vec<EventType>>,
+    pub packet_types: Option<Vec<PacketTypeLabel>>,
+    pub session_id: Option<u64>,
+    pub run_id: Option<u64>,
+    /// Time range, UNIX timestamps in milliseconds (inclusive)
+    pub from_ms: Option<u64>,
+    pub to_ms: Option<u64>,
+    pub source: Option<NodeId>,
+    pub destination: Option<NodeId>,
+    /// A node the route of the packet goes through
+    pub hop: Option<NodeId>,
+    /// Case-insensitive text searched in the content of the host messages
+    pub text: Option<String>,
+    /// Number of matching events to skip, oldest first
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+/// A page of the events matching a query.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventPage {
+    /// Number of events matching the query
+    pub total: usize,
+    pub counts_by_event_type: HashMap<EventType, usize>,
+    pub counts_by_packet_type: HashMap<PacketTypeLabel, usize>,
+    pub offset: usize,
+    pub events: Vec<LoggedEvent>,
+}
@@ -16,6 +16,7 @@ use std::thread;
 use wg_2024::controller::{DroneCommand, DroneEvent};
 use wg_2024::drone::Drone;
 use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
 
 pub fn initialize_network(state: &mut SimulationState) -> Result<(), NetworkError> {
     info!("Initializing network...");
@@ -39,7 +40,7 @@ pub fn init_inter_node_channels(state: &mut SimulationState) -> Result<(), Netwo
         .clone();
 
     for drone in &config.drone {
-        let (sender, receiver) = unbounded();
+        let (sender, receiver) = new_packet_channel(state, drone.id);
         state
             .get_inter_node_channels_mut()
             .insert(drone.id, (sender, receiver));
@@ -48,7 +49,7 @@ pub fn init_inter_node_channels(state: &mut SimulationState) -> Result<(), Netwo
             .insert_node(drone.id, wg_2024::packet::NodeType::Drone);
     }
     for client in &config.client {
-        let (sender, receiver) = unbounded();
+        let (sender, receiver) = new_packet_channel(state, client.id);
         state
             .get_inter_node_channels_mut()
             .insert(client.id, (sender, receiver));
@@ -57,7 +58,7 @@ pub fn init_inter_node_channels(state: &mut SimulationState) -> Result<(), Netwo
             .insert_node(client.id, wg_2024::packet::NodeType::Client);
     }
     for server in &config.server {
-        let (sender, receiver) = unbounded();
+        let (sender, receiver) = new_packet_channel(state, server.id);
         state
             .get_inter_node_channels_mut()
             .insert(server.id, (sender, receiver));
@@ -69,6 +70,19 @@ pub fn init_inter_node_channels(state: &mut SimulationState) -> Result<(), Netwo
     Ok(())
 }
 
+/// Creates the inbound packet channel of `node_id`, tapped by the sniffer when it is enabled.
+fn new_packet_channel(
+    state: &SimulationState,
+    node_id: NodeId,
+) -> (Sender<Packet>, Receiver<Packet>) {
+    let (sender, receiver) = unbounded();
+    if state.get_sniffer_enabled() {
+        (state.get_sniffer().tap(node_id, sender), receiver)
+    } else {
+        (sender, receiver)
+    }
+}
+
 pub fn initialize_drones(state: &mut SimulationState) -> Result<(), NetworkError> {
     let drone_factories: Vec<DroneFactory> = drone_factories![
         rusty_drones::RustyDrone,
@@ -381,7 +395,7 @@ pub fn create_new_drone(
         .get_drone_controller_channels_mut()
         .insert(drone_id, (cmd_tx.clone(), evt_rx));
 
-    let (packet_sender, packet_receiver) = unbounded();
+    let (packet_sender, packet_receiver) = new_packet_channel(state, drone_id);
     state
         .get_inter_node_channels_mut()
         .insert(drone_id, (packet_sender.clone(), packet_receiver.clone()));
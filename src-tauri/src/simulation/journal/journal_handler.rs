@@ -0,0 +1,502 @@
+use crate::error::NetworkError;
+use crate::simulation::configs::configs_handler;
+use crate::simulation::journal::{
+    Journal, JournalEntry, JournalOperation, JournalView, NodeKind, ScenarioScript, ScenarioStep,
+    MAX_JOURNAL_ENTRIES, SCENARIO_FORMAT_VERSION,
+};
+use crate::simulation::state::{SimulationState, SimulationStatus};
+use crate::simulation::topology::{GraphState, NodeMetadata};
+use crate::utils::now_millis;
+use log::info;
+use wg_2024::network::NodeId;
+use wg_2024::packet::NodeType;
+
+impl From<NodeKind> for NodeType {
+    fn from(kind: NodeKind) -> Self {
+        match kind {
+            NodeKind::Drone => NodeType::Drone,
+            NodeKind::Client => NodeType::Client,
+            NodeKind::Server => NodeType::Server,
+        }
+    }
+}
+
+impl JournalOperation {
+    /// Describes the removal of `node_id` from `graph`.
+    pub fn remove_node(graph: &GraphState, node_id: NodeId) -> Result<Self, NetworkError> {
+        let (node_type, pdr) = match graph.get_node_type(node_id) {
+            Some(NodeMetadata::Drone(drone)) => {
+                (NodeKind::Drone, Some(pdr_percent(drone.get_pdr())))
+            }
+            Some(NodeMetadata::Client) => (NodeKind::Client, None),
+            Some(NodeMetadata::Server) => (NodeKind::Server, None),
+            None => return Err(NetworkError::NodeNotFound(node_id.to_string())),
+        };
+        let mut neighbors = graph.get_neighbors(node_id);
+        neighbors.sort_unstable();
+        Ok(JournalOperation::RemoveNode {
+            node_id,
+            node_type,
+            neighbors,
+            pdr,
+        })
+    }
+
+    /// Describes setting the PDR of `drone_id` in `graph` to `pdr`.
+    pub fn set_pdr(graph: &GraphState, drone_id: NodeId, pdr: u8) -> Result<Self, NetworkError> {
+        match graph.get_node_type(drone_id) {
+            Some(NodeMetadata::Drone(drone)) => Ok(JournalOperation::SetPdr {
+                drone_id,
+                pdr,
+                previous: pdr_percent(drone.get_pdr()) as u8,
+            }),
+            _ => Err(NetworkError::NodeIsNotDrone(drone_id)),
+        }
+    }
+
+    /// Returns the operation that undoes this one.
+    pub fn inverse(&self) -> Self {
+        match self.clone() {
+            JournalOperation::AddNode {
+                node_id,
+                node_type,
+                neighbors,
+                pdr,
+            } => JournalOperation::RemoveNode {
+                node_id,
+                node_type,
+                neighbors,
+                pdr,
+            },
+            JournalOperation::RemoveNode {
+                node_id,
+                node_type,
+                neighbors,
+                pdr,
+            } => JournalOperation::AddNode {
+                node_id,
+                node_type,
+                neighbors,
+                pdr,
+            },
+            JournalOperation::AddEdge { node1_id, node2_id } => {
+                JournalOperation::RemoveEdge { node1_id, node2_id }
+            }
+            JournalOperation::RemoveEdge { node1_id, node2_id } => {
+                JournalOperation::AddEdge { node1_id, node2_id }
+            }
+            JournalOperation::SetPdr {
+                drone_id,
+                pdr,
+                previous,
+            } => JournalOperation::SetPdr {
+                drone_id,
+                pdr: previous,
+                previous: pdr,
+            },
+        }
+    }
+}
+
+impl Journal {
+    /// Records an applied operation. The undone operations cannot be redone anymore.
+    pub fn record(&mut self, entry: JournalEntry) {
+        self.undone.clear();
+        self.done.push_back(entry);
+        if self.done.len() > MAX_JOURNAL_ENTRIES {
+            self.done.pop_front();
+        }
+    }
+
+    /// Forgets every operation, after the topology was changed outside of the journal.
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+
+    /// Forgets the operations applied to the running network.
+    pub fn discard_runtime_entries(&mut self) {
+        self.done.retain(|entry| entry.run_id.is_none());
+        self.undone.retain(|entry| entry.run_id.is_none());
+    }
+
+    pub fn view(&self) -> JournalView {
+        JournalView {
+            done: self.done.iter().cloned().collect(),
+            undone: self.undone.iter().rev().cloned().collect(),
+        }
+    }
+
+    /// Returns the applied operations as a scenario script.
+    pub fn scenario(&self) -> ScenarioScript {
+        let config_start = self
+            .done
+            .iter()
+            .find_map(|entry| entry.timestamp)
+            .unwrap_or(0);
+        ScenarioScript {
+            format_version: SCENARIO_FORMAT_VERSION,
+            steps: self
+                .done
+                .iter()
+                .map(|entry| ScenarioStep {
+                    offset_ms: match (entry.run_time_ms, entry.timestamp) {
+                        (Some(run_time_ms), _) => run_time_ms,
+                        (None, Some(timestamp)) => timestamp.saturating_sub(config_start),
+                        (None, None) => 0,
+                    },
+                    runtime: entry.run_id.is_some(),
+                    operation: entry.operation.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Applies `operation` to the running network, or to the loaded configuration, and records
+/// it in the journal.
+pub fn execute(
+    state: &mut SimulationState,
+    operation: JournalOperation,
+) -> Result<(), NetworkError> {
+    apply_operation(state, &operation)?;
+    let entry = new_entry(state, operation);
+    state.get_journal_mut().record(entry);
+    Ok(())
+}
+
+/// Undoes the last applied operation and returns it.
+pub fn undo(state: &mut SimulationState) -> Result<JournalOperation, NetworkError> {
+    let entry = state
+        .get_journal()
+        .done
+        .back()
+        .cloned()
+        .ok_or_else(|| NetworkError::InvalidOperation("Nothing to undo".to_string()))?;
+    check_run(state, &entry)?;
+
+    apply_operation(state, &entry.operation.inverse())?;
+    info!("Undone {:?}", entry.operation);
+
+    let journal = state.get_journal_mut();
+    journal.done.pop_back();
+    journal.undone.push(entry.clone());
+    Ok(entry.operation)
+}
+
+/// Applies again the last undone operation and returns it.
+pub fn redo(state: &mut SimulationState) -> Result<JournalOperation, NetworkError> {
+    let entry = state
+        .get_journal()
+        .undone
+        .last()
+        .cloned()
+        .ok_or_else(|| NetworkError::InvalidOperation("Nothing to redo".to_string()))?;
+    check_run(state, &entry)?;
+
+    apply_operation(state, &entry.operation)?;
+    info!("Redone {:?}", entry.operation);
+
+    let redone = new_entry(state, entry.operation.clone());
+    let journal = state.get_journal_mut();
+    journal.undone.pop();
+    journal.done.push_back(redone);
+    Ok(entry.operation)
+}
+
+/// Returns the ID the next added node receives.
+pub fn next_node_id(state: &SimulationState) -> Result<NodeId, NetworkError> {
+    if state.get_status() == SimulationStatus::Running {
+        Ok(state.get_graph().get_next_node_id())
+    } else {
+        configs_handler::next_config_node_id(state)
+    }
+}
+
+/// Applies `operation` through the same paths as the topology commands.
+fn apply_operation(
+    state: &mut SimulationState,
+    operation: &JournalOperation,
+) -> Result<(), NetworkError> {
+    if state.get_status() == SimulationStatus::Running {
+        apply_to_network(state, operation)
+    } else {
+        apply_to_config(state, operation)
+    }
+}
+
+fn apply_to_network(
+    state: &mut SimulationState,
+    operation: &JournalOperation,
+) -> Result<(), NetworkError> {
+    use crate::simulation::controller;
+
+    match operation.clone() {
+        JournalOperation::AddNode {
+            node_id,
+            node_type: NodeKind::Drone,
+            neighbors,
+            pdr,
+        } => {
+            let pdr = pdr.ok_or(NetworkError::InvalidPdr(0))?;
+            controller::add_drone_with_id(state, node_id, neighbors, pdr)
+        }
+        JournalOperation::AddNode { .. } => Err(NetworkError::InvalidOperation(
+            "Cannot add a client or server node during the simulation".to_string(),
+        )),
+        JournalOperation::RemoveNode {
+            node_id,
+            node_type: NodeKind::Drone,
+            ..
+        } => controller::crash_drone(state, node_id),
+        JournalOperation::RemoveNode { node_id, .. } => Err(NetworkError::NodeIsNotDrone(node_id)),
+        JournalOperation::AddEdge { node1_id, node2_id } => {
+            controller::add_edge(state, node1_id, node2_id)
+        }
+        JournalOperation::RemoveEdge { node1_id, node2_id } => {
+            controller::remove_edge(state, node1_id, node2_id)
+        }
+        JournalOperation::SetPdr { drone_id, pdr, .. } => controller::set_pdr(state, drone_id, pdr),
+    }
+}
+
+fn apply_to_config(
+    state: &mut SimulationState,
+    operation: &JournalOperation,
+) -> Result<(), NetworkError> {
+    // The configuration functions do not check that the links exist, or do not exist yet
+    match *operation {
+        JournalOperation::AddEdge { node1_id, node2_id } => state
+            .get_topology()
+            .into_owned()
+            .add_edge(node1_id, node2_id)?,
+        JournalOperation::RemoveEdge { node1_id, node2_id } => state
+            .get_topology()
+            .into_owned()
+            .remove_edge(node1_id, node2_id)?,
+        _ => {}
+    }
+
+    match operation.clone() {
+        JournalOperation::AddNode {
+            node_id,
+            node_type,
+            neighbors,
+            pdr,
+        } => configs_handler::insert_node_into_config(
+            state,
+            node_id,
+            node_type.into(),
+            neighbors,
+            pdr,
+        ),
+        JournalOperation::RemoveNode { node_id, .. } => {
+            configs_handler::remove_node_from_config(state, node_id)
+        }
+        JournalOperation::AddEdge { node1_id, node2_id } => {
+            configs_handler::add_edge_to_config(state, node1_id, node2_id)
+        }
+        JournalOperation::RemoveEdge { node1_id, node2_id } => {
+            configs_handler::remove_edge_from_config(state, node1_id, node2_id)
+        }
+        JournalOperation::SetPdr { drone_id, pdr, .. } => {
+            configs_handler::set_pdr_in_config(state, drone_id, pdr)
+        }
+    }
+}
+
+/// The run the edits are applied to, `None` when the configuration is edited.
+fn current_run(state: &SimulationState) -> Option<u64> {
+    (state.get_status() == SimulationStatus::Running).then(|| state.get_run_id())
+}
+
+/// Stamps `operation` with the time of the run it is applied to, or with the wall-clock time
+/// for the edits of the configuration.
+fn new_entry(state: &SimulationState, operation: JournalOperation) -> JournalEntry {
+    let run_id = current_run(state);
+    JournalEntry {
+        operation,
+        run_id,
+        run_time_ms: run_id.map(|_| state.get_clock().now_ms()),
+        timestamp: run_id.is_none().then(now_millis),
+    }
+}
+
+/// Ensures `entry` was applied to what is edited now: the same run, or the configuration.
+fn check_run(state: &SimulationState, entry: &JournalEntry) -> Result<(), NetworkError> {
+    if entry.run_id == current_run(state) {
+        return Ok(());
+    }
+    Err(NetworkError::InvalidOperation(match entry.run_id {
+        Some(run_id) => format!("The operation was applied to run {}", run_id),
+        None => "The operation was applied to the configuration".to_string(),
+    }))
+}
+
+fn pdr_percent(pdr: f32) -> u32 {
+    (pdr * 100.0).round() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_edge(node1_id: NodeId, node2_id: NodeId) -> JournalOperation {
+        JournalOperation::AddEdge { node1_id, node2_id }
+    }
+
+    fn config_entry(operation: JournalOperation, timestamp: u64) -> JournalEntry {
+        JournalEntry {
+            operation,
+            run_id: None,
+            run_time_ms: None,
+            timestamp: Some(timestamp),
+        }
+    }
+
+    fn runtime_entry(operation: JournalOperation, run_time_ms: u64) -> JournalEntry {
+        JournalEntry {
+            operation,
+            run_id: Some(1),
+            run_time_ms: Some(run_time_ms),
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn inverse_swaps_additions_and_removals() {
+        let add = JournalOperation::AddNode {
+            node_id: 4,
+            node_type: NodeKind::Drone,
+            neighbors: vec![1, 2],
+            pdr: Some(10),
+        };
+        assert_eq!(
+            add.inverse(),
+            JournalOperation::RemoveNode {
+                node_id: 4,
+                node_type: NodeKind::Drone,
+                neighbors: vec![1, 2],
+                pdr: Some(10),
+            }
+        );
+        assert_eq!(
+            add_edge(1, 2).inverse(),
+            JournalOperation::RemoveEdge {
+                node1_id: 1,
+                node2_id: 2
+            }
+        );
+    }
+
+    #[test]
+    fn inverse_restores_the_previous_pdr() {
+        let set_pdr = JournalOperation::SetPdr {
+            drone_id: 3,
+            pdr: 40,
+            previous: 5,
+        };
+        assert_eq!(
+            set_pdr.inverse(),
+            JournalOperation::SetPdr {
+                drone_id: 3,
+                pdr: 5,
+                previous: 40,
+            }
+        );
+    }
+
+    #[test]
+    fn inverse_of_inverse_is_the_operation() {
+        let operations = [
+            JournalOperation::RemoveNode {
+                node_id: 7,
+                node_type: NodeKind::Client,
+                neighbors: vec![1],
+                pdr: None,
+            },
+            JournalOperation::RemoveEdge {
+                node1_id: 2,
+                node2_id: 3,
+            },
+            JournalOperation::SetPdr {
+                drone_id: 1,
+                pdr: 0,
+                previous: 100,
+            },
+        ];
+        for operation in operations {
+            assert_eq!(operation.inverse().inverse(), operation);
+        }
+    }
+
+    #[test]
+    fn record_clears_the_undone_operations() {
+        let mut journal = Journal::default();
+        journal.undone.push(config_entry(add_edge(1, 2), 0));
+
+        journal.record(config_entry(add_edge(2, 3), 0));
+
+        assert!(journal.undone.is_empty());
+        assert_eq!(journal.done.len(), 1);
+    }
+
+    #[test]
+    fn record_forgets_the_oldest_operation_past_the_limit() {
+        let mut journal = Journal::default();
+        for timestamp in 0..=MAX_JOURNAL_ENTRIES as u64 {
+            journal.record(config_entry(add_edge(1, 2), timestamp));
+        }
+
+        assert_eq!(journal.done.len(), MAX_JOURNAL_ENTRIES);
+        assert_eq!(journal.done.front().unwrap().timestamp, Some(1));
+        assert_eq!(
+            journal.done.back().unwrap().timestamp,
+            Some(MAX_JOURNAL_ENTRIES as u64)
+        );
+    }
+
+    #[test]
+    fn discard_runtime_entries_keeps_the_configuration_edits() {
+        let mut journal = Journal::default();
+        journal.done.push_back(config_entry(add_edge(1, 2), 0));
+        journal.done.push_back(runtime_entry(add_edge(2, 3), 0));
+        journal.undone.push(runtime_entry(add_edge(3, 4), 0));
+        journal.undone.push(config_entry(add_edge(4, 5), 0));
+
+        journal.discard_runtime_entries();
+
+        let view = journal.view();
+        assert_eq!(view.done.len(), 1);
+        assert_eq!(view.done[0].operation, add_edge(1, 2));
+        assert_eq!(view.undone.len(), 1);
+        assert_eq!(view.undone[0].operation, add_edge(4, 5));
+    }
+
+    #[test]
+    fn scenario_offsets_follow_the_run_clock_and_the_first_configuration_edit() {
+        let mut journal = Journal::default();
+        journal.record(config_entry(add_edge(1, 2), 5_000));
+        journal.record(config_entry(add_edge(2, 3), 7_500));
+        journal.record(runtime_entry(add_edge(3, 4), 120));
+        journal.record(runtime_entry(add_edge(4, 5), 900));
+
+        let scenario = journal.scenario();
+
+        assert_eq!(scenario.format_version, SCENARIO_FORMAT_VERSION);
+        let offsets: Vec<(u64, bool)> = scenario
+            .steps
+            .iter()
+            .map(|step| (step.offset_ms, step.runtime))
+            .collect();
+        assert_eq!(
+            offsets,
+            vec![(0, false), (2_500, false), (120, true), (900, true)]
+        );
+    }
+
+    #[test]
+    fn scenario_of_an_empty_journal_has_no_steps() {
+        assert!(Journal::default().scenario().steps.is_empty());
+    }
+}
@@ -0,0 +1,103 @@
+pub mod journal_handler;
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use wg_2024::network::NodeId;
+
+/// Maximum number of operations that can be undone. The oldest operation is forgotten first.
+const MAX_JOURNAL_ENTRIES: usize = 1_000;
+
+/// Version of the scenario script format written by `export_scenario`.
+pub const SCENARIO_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodeKind {
+    Drone,
+    Client,
+    Server,
+}
+
+/// A topology edit as recorded in the journal, with everything needed to undo it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum JournalOperation {
+    AddNode {
+        node_id: NodeId,
+        node_type: NodeKind,
+        neighbors: Vec<NodeId>,
+        /// PDR in percent, for the drones
+        pdr: Option<u32>,
+    },
+    /// At runtime, only drones can be removed: removing a drone crashes it
+    RemoveNode {
+        node_id: NodeId,
+        node_type: NodeKind,
+        /// The neighbors of the node before the removal
+        neighbors: Vec<NodeId>,
+        /// PDR in percent of the drone before the removal
+        pdr: Option<u32>,
+    },
+    AddEdge {
+        node1_id: NodeId,
+        node2_id: NodeId,
+    },
+    RemoveEdge {
+        node1_id: NodeId,
+        node2_id: NodeId,
+    },
+    SetPdr {
+        drone_id: NodeId,
+        pdr: u8,
+        previous: u8,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub operation: JournalOperation,
+    /// The run the operation was applied to, `None` for the edits of the configuration
+    pub run_id: Option<u64>,
+    /// Run-relative time (milliseconds) of the operation, for the operations applied to a run
+    pub run_time_ms: Option<u64>,
+    /// UNIX timestamp (milliseconds) of the operation, for the edits of the configuration
+    pub timestamp: Option<u64>,
+}
+
+/// Undo and redo stacks of the topology edits.
+///
+/// The edits of the configuration and of the running network share the stacks: the runtime
+/// edits are discarded when the run stops, and the whole journal when a configuration is
+/// loaded.
+#[derive(Debug, Default)]
+pub struct Journal {
+    /// Applied operations, oldest first
+    done: VecDeque<JournalEntry>,
+    /// Undone operations, most recently undone last
+    undone: Vec<JournalEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalView {
+    /// The operations that can be undone, oldest first
+    pub done: Vec<JournalEntry>,
+    /// The operations that can be redone, next one first
+    pub undone: Vec<JournalEntry>,
+}
+
+/// The applied operations of the journal, in a form that can be replayed on the same
+/// configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioScript {
+    pub format_version: u32,
+    pub steps: Vec<ScenarioStep>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioStep {
+    /// Milliseconds since the start of the run for the runtime steps, since the first
+    /// configuration step for the others
+    pub offset_ms: u64,
+    /// If the step is applied to the running network rather than to the configuration
+    pub runtime: bool,
+    pub operation: JournalOperation,
+}
@@ -0,0 +1,261 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NodeType, Packet, PacketType};
+
+/// Maximum number of floods kept by the analyzer. The oldest flood is evicted first.
+const MAX_FLOODS: usize = 1000;
+
+/// Window used to detect flood storms when the hosts do not rediscover the network.
+const DEFAULT_STORM_WINDOW_MS: u64 = 10_000;
+
+/// Number of floods started by the same initiator within `DEFAULT_STORM_WINDOW_MS` to flag a
+/// storm when the hosts do not rediscover the network.
+const DEFAULT_STORM_THRESHOLD: usize = 5;
+
+/// Length of the storm window, in discovery intervals.
+const STORM_WINDOW_INTERVALS: u32 = 5;
+
+/// How storms are detected: `threshold` floods started by the same initiator within `window_ms`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StormWindow {
+    pub window_ms: u64,
+    pub threshold: usize,
+}
+
+impl StormWindow {
+    /// Flags the initiators flooding at least twice as often as the periodic discovery of
+    /// `discovery_interval`, which starts a flood at each interval.
+    pub fn for_discovery_interval(discovery_interval: Option<Duration>) -> Self {
+        match discovery_interval.filter(|interval| !interval.is_zero()) {
+            Some(interval) => StormWindow {
+                window_ms: (interval * STORM_WINDOW_INTERVALS).as_millis() as u64,
+                // Both ends of the window may see a periodic flood
+                threshold: 2 * (STORM_WINDOW_INTERVALS as usize + 1),
+            },
+            None => StormWindow {
+                window_ms: DEFAULT_STORM_WINDOW_MS,
+                threshold: DEFAULT_STORM_THRESHOLD,
+            },
+        }
+    }
+}
+
+/// Groups the flood requests and responses by `(initiator, flood_id)`.
+#[derive(Debug, Default)]
+pub struct FloodAnalyzer {
+    floods: HashMap<(NodeId, u64), FloodRecord>,
+    /// Keys of the floods, in the order they were first seen
+    order: VecDeque<(NodeId, u64)>,
+}
+
+#[derive(Debug)]
+struct FloodRecord {
+    started_at: u64,
+    last_response_at: Option<u64>,
+    /// Number of flood requests sent by the drones
+    transmissions: u64,
+    /// Drones that forwarded the request
+    forwarders: HashSet<NodeId>,
+    /// Distinct path traces ending at each node: each one is a reception of the request
+    receptions: HashMap<NodeId, HashSet<Vec<NodeId>>>,
+    /// Distinct path traces of the responses
+    responses: HashSet<Vec<NodeId>>,
+}
+
+/// Analytics of a single flood.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FloodStats {
+    pub initiator: NodeId,
+    pub flood_id: u64,
+    /// Run-relative timestamp in milliseconds of the first request seen
+    pub started_at: u64,
+    pub transmissions: u64,
+    pub forwarders: usize,
+    /// Average number of requests sent by each forwarding drone
+    pub fan_out: f32,
+    /// Number of times each node received the request after the first one
+    pub duplicates: HashMap<NodeId, u64>,
+    pub responses: usize,
+    pub time_to_last_response_ms: Option<u64>,
+    pub reached: Vec<NodeId>,
+    /// Fraction of the network reached by the flood
+    pub coverage: f32,
+}
+
+/// An initiator that started too many floods in a short time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FloodStorm {
+    pub initiator: NodeId,
+    pub floods_in_window: usize,
+    pub window_ms: u64,
+    pub min_interval_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FloodReport {
+    pub floods: Vec<FloodStats>,
+    pub storms: Vec<FloodStorm>,
+}
+
+impl FloodAnalyzer {
+    /// Records a packet sent by `node_id`, ignoring everything but flood requests and responses.
+    pub fn record_packet(&mut self, node_id: NodeId, packet: &Packet, now_ms: u64) {
+        match &packet.pack_type {
+            PacketType::FloodRequest(request) => {
+                let path = node_ids(&request.path_trace);
+                let record = self.record_for((request.initiator_id, request.flood_id), now_ms);
+                record.transmissions += 1;
+                record.forwarders.insert(node_id);
+                record.add_reception(path);
+            }
+            PacketType::FloodResponse(response) => {
+                let path = node_ids(&response.path_trace);
+                let Some(&initiator) = path.first() else {
+                    return;
+                };
+                let record = self.record_for((initiator, response.flood_id), now_ms);
+                if record.responses.insert(path.clone()) {
+                    record.add_reception(path);
+                }
+                record.last_response_at = Some(now_ms);
+            }
+            _ => {}
+        }
+    }
+
+    fn record_for(&mut self, key: (NodeId, u64), now_ms: u64) -> &mut FloodRecord {
+        if !self.floods.contains_key(&key) {
+            self.order.push_back(key);
+            if self.order.len() > MAX_FLOODS {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.floods.remove(&oldest);
+                }
+            }
+        }
+
+        self.floods.entry(key).or_insert_with(|| FloodRecord {
+            started_at: now_ms,
+            last_response_at: None,
+            transmissions: 0,
+            forwarders: HashSet::new(),
+            receptions: HashMap::new(),
+            responses: HashSet::new(),
+        })
+    }
+
+    /// Returns the analytics of the floods (most recent first) and the detected storms.
+    ///
+    /// # Parameters
+    /// - `initiator`: only return the floods started by this node, if set.
+    /// - `network_size`: the number of nodes in the network, used to compute the coverage.
+    /// - `storm_window`: how the storms are detected.
+    pub fn report(
+        &self,
+        initiator: Option<NodeId>,
+        network_size: usize,
+        storm_window: StormWindow,
+        now_ms: u64,
+    ) -> FloodReport {
+        let floods = self
+            .order
+            .iter()
+            .rev()
+            .filter(|(init, _)| initiator.is_none_or(|i| i == *init))
+            .filter_map(|key| {
+                self.floods
+                    .get(key)
+                    .map(|record| record.stats(*key, network_size))
+            })
+            .collect();
+
+        FloodReport {
+            floods,
+            storms: self.storms(storm_window, now_ms),
+        }
+    }
+
+    fn storms(&self, storm_window: StormWindow, now_ms: u64) -> Vec<FloodStorm> {
+        let mut starts: HashMap<NodeId, Vec<u64>> = HashMap::new();
+        for (key, record) in &self.floods {
+            if record.started_at + storm_window.window_ms >= now_ms {
+                starts.entry(key.0).or_default().push(record.started_at);
+            }
+        }
+
+        let mut storms: Vec<FloodStorm> = starts
+            .into_iter()
+            .filter(|(_, starts)| starts.len() >= storm_window.threshold)
+            .map(|(initiator, mut starts)| {
+                starts.sort_unstable();
+                FloodStorm {
+                    initiator,
+                    floods_in_window: starts.len(),
+                    window_ms: storm_window.window_ms,
+                    min_interval_ms: starts
+                        .windows(2)
+                        .map(|w| w[1] - w[0])
+                        .min()
+                        .unwrap_or_default(),
+                }
+            })
+            .collect();
+        storms.sort_by_key(|storm| storm.initiator);
+        storms
+    }
+}
+
+impl FloodRecord {
+    fn add_reception(&mut self, path: Vec<NodeId>) {
+        if let Some(&last) = path.last() {
+            self.receptions.entry(last).or_default().insert(path);
+        }
+    }
+
+    fn stats(&self, (initiator, flood_id): (NodeId, u64), network_size: usize) -> FloodStats {
+        let mut reached: Vec<NodeId> = self
+            .receptions
+            .values()
+            .flatten()
+            .flatten()
+            .copied()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        reached.sort_unstable();
+
+        FloodStats {
+            initiator,
+            flood_id,
+            started_at: self.started_at,
+            transmissions: self.transmissions,
+            forwarders: self.forwarders.len(),
+            fan_out: if self.forwarders.is_empty() {
+                0.0
+            } else {
+                self.transmissions as f32 / self.forwarders.len() as f32
+            },
+            duplicates: self
+                .receptions
+                .iter()
+                .filter(|(_, paths)| paths.len() > 1)
+                .map(|(&node, paths)| (node, paths.len() as u64 - 1))
+                .collect(),
+            responses: self.responses.len(),
+            time_to_last_response_ms: self
+                .last_response_at
+                .map(|last| last.saturating_sub(self.started_at)),
+            coverage: if network_size == 0 {
+                0.0
+            } else {
+                (reached.len() as f32 / network_size as f32).min(1.0)
+            },
+            reached,
+        }
+    }
+}
+
+fn node_ids(path_trace: &[(NodeId, NodeType)]) -> Vec<NodeId> {
+    path_trace.iter().map(|(id, _)| *id).collect()
+}
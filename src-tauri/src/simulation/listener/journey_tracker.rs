@@ -0,0 +1,250 @@
+use crate::simulation::metrics::edges::next_hop_of;
+use crate::simulation::metrics::{NackTypeLabel, PacketTypeLabel};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use wg_2024::network::NodeId;
+use wg_2024::packet::{Packet, PacketType};
+
+/// Maximum number of journeys kept by the tracker. The oldest journey is evicted first.
+const MAX_JOURNEYS: usize = 50_000;
+
+/// Identifies a packet across hops.
+///
+/// `source` is the node that owns the session: the sender of the fragments, which is
+/// also the destination of the Acks and NACKs of the session. `index` is the fragment
+/// index, or the flood id for flood responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct JourneyKey {
+    pub source: NodeId,
+    pub session_id: u64,
+    pub packet_type: PacketTypeLabel,
+    pub index: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HopAction {
+    Forwarded {
+        to: NodeId,
+    },
+    Dropped,
+    Shortcut,
+    /// The simulation controller could not deliver the packet
+    ShortcutFailed,
+}
+
+/// A node that handled the packet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JourneyHop {
+    /// Order in which the hop was recorded, shared by every journey
+    pub seq: u64,
+    pub node: NodeId,
+    /// The hop index reported in the routing header
+    pub hop_index: usize,
+    /// Run-relative timestamp in milliseconds
+    pub timestamp: u64,
+    pub action: HopAction,
+}
+
+/// The hop-by-hop journey of a packet, including every retransmission.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PacketJourney {
+    #[serde(flatten)]
+    pub key: JourneyKey,
+    /// The last route seen for the packet
+    pub route: Vec<NodeId>,
+    pub hops: Vec<JourneyHop>,
+    /// Number of times the packet was sent along the route
+    pub attempts: u64,
+    pub dropped_by: Vec<NodeId>,
+    pub shortcut_used: bool,
+    pub delivered: bool,
+    /// The type of the last NACK seen, for NACKs
+    pub nack_type: Option<NackTypeLabel>,
+    /// Whether the first node of the route reports the packet itself
+    #[serde(skip)]
+    reported_by_origin: bool,
+}
+
+/// A route seen in the routing header of the packets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObservedRoute {
+    pub hops: Vec<NodeId>,
+    /// Number of packets sent along the route
+    pub packets: u64,
+    /// Run-relative timestamp in milliseconds of the last hop recorded on the route
+    pub last_seen: u64,
+}
+
+/// Links the events reported by the drones across hops.
+#[derive(Debug, Default)]
+pub struct JourneyTracker {
+    journeys: HashMap<JourneyKey, PacketJourney>,
+    /// Keys of the journeys, in the order they were first seen
+    order: VecDeque<JourneyKey>,
+    next_seq: u64,
+}
+
+impl JourneyKey {
+    /// Returns the key of a routed packet, `None` for flood requests.
+    pub fn of(packet: &Packet) -> Option<Self> {
+        let header = &packet.routing_header;
+        let (source, index) = match &packet.pack_type {
+            PacketType::MsgFragment(fragment) => (header.source()?, fragment.fragment_index),
+            PacketType::Ack(ack) => (header.destination()?, ack.fragment_index),
+            PacketType::Nack(nack) => (header.destination()?, nack.fragment_index),
+            PacketType::FloodResponse(response) => (header.source()?, response.flood_id),
+            PacketType::FloodRequest(_) => return None,
+        };
+        Some(JourneyKey {
+            source,
+            session_id: packet.session_id,
+            packet_type: PacketTypeLabel::from(&packet.pack_type),
+            index,
+        })
+    }
+}
+
+impl JourneyTracker {
+    /// Records a packet sent by the drone `node_id` to its next hop.
+    pub fn record_sent(&mut self, node_id: NodeId, packet: &Packet, now_ms: u64) {
+        if let Some(to) = next_hop_of(&packet.routing_header, node_id) {
+            self.record(node_id, packet, now_ms, HopAction::Forwarded { to });
+        }
+    }
+
+    /// Records a packet dropped by the drone `node_id`.
+    pub fn record_dropped(&mut self, node_id: NodeId, packet: &Packet, now_ms: u64) {
+        self.record(node_id, packet, now_ms, HopAction::Dropped);
+    }
+
+    /// Records a packet sent through the simulation controller by the node `node_id`.
+    pub fn record_shortcut(
+        &mut self,
+        node_id: NodeId,
+        packet: &Packet,
+        delivered: bool,
+        now_ms: u64,
+    ) {
+        let action = if delivered {
+            HopAction::Shortcut
+        } else {
+            HopAction::ShortcutFailed
+        };
+        self.record(node_id, packet, now_ms, action);
+    }
+
+    fn record(&mut self, node_id: NodeId, packet: &Packet, now_ms: u64, action: HopAction) {
+        let Some(key) = JourneyKey::of(packet) else {
+            return;
+        };
+        let header = &packet.routing_header;
+
+        if !self.journeys.contains_key(&key) {
+            self.order.push_back(key);
+            if self.order.len() > MAX_JOURNEYS {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.journeys.remove(&oldest);
+                }
+            }
+        }
+        let journey = self.journeys.entry(key).or_insert_with(|| PacketJourney {
+            key,
+            route: header.hops.clone(),
+            hops: Vec::new(),
+            attempts: 0,
+            dropped_by: Vec::new(),
+            shortcut_used: false,
+            delivered: false,
+            nack_type: None,
+            reported_by_origin: false,
+        });
+        if let PacketType::Nack(nack) = &packet.pack_type {
+            journey.nack_type = Some(NackTypeLabel::from(&nack.nack_type));
+        }
+
+        // A new attempt starts every time a drone originates the packet, or, for packets
+        // originated by hosts (which do not report them to the listener), when it reaches
+        // the first drone
+        let position = header.hops.iter().position(|&hop| hop == node_id);
+        if position == Some(0) {
+            journey.reported_by_origin = true;
+        }
+        if position == Some(0) || (position == Some(1) && !journey.reported_by_origin) {
+            journey.attempts += 1;
+            journey.route = header.hops.clone();
+
+            // The host does not report the packet, but it must have sent it to this drone
+            if position == Some(1) {
+                journey.hops.push(JourneyHop {
+                    seq: self.next_seq,
+                    node: header.hops[0],
+                    hop_index: header.hop_index.saturating_sub(1),
+                    timestamp: now_ms,
+                    action: HopAction::Forwarded { to: node_id },
+                });
+                self.next_seq += 1;
+            }
+        }
+
+        match action {
+            HopAction::Forwarded { to } if Some(to) == header.destination() => {
+                journey.delivered = true;
+            }
+            HopAction::Dropped => journey.dropped_by.push(node_id),
+            HopAction::Shortcut => {
+                journey.shortcut_used = true;
+                journey.delivered = true;
+            }
+            _ => {}
+        }
+
+        journey.hops.push(JourneyHop {
+            seq: self.next_seq,
+            node: node_id,
+            hop_index: header.hop_index,
+            timestamp: now_ms,
+            action,
+        });
+        self.next_seq += 1;
+    }
+
+    pub fn journey(&self, key: &JourneyKey) -> Option<&PacketJourney> {
+        self.journeys.get(key)
+    }
+
+    /// Returns every journey of the session `(source, session_id)`, ordered by first hop.
+    pub fn session_journeys(&self, source: NodeId, session_id: u64) -> Vec<&PacketJourney> {
+        let mut journeys: Vec<&PacketJourney> = self
+            .journeys
+            .values()
+            .filter(|j| j.key.source == source && j.key.session_id == session_id)
+            .collect();
+        journeys.sort_by_key(|j| j.hops.first().map(|hop| hop.seq));
+        journeys
+    }
+
+    /// Returns the routes of the tracked packets, most recently used first.
+    pub fn observed_routes(&self) -> Vec<ObservedRoute> {
+        let mut routes: HashMap<&[NodeId], ObservedRoute> = HashMap::new();
+        for journey in self.journeys.values() {
+            let last_seen = journey.hops.last().map_or(0, |hop| hop.timestamp);
+            let route = routes
+                .entry(journey.route.as_slice())
+                .or_insert_with(|| ObservedRoute {
+                    hops: journey.route.clone(),
+                    packets: 0,
+                    last_seen,
+                });
+            route.packets += 1;
+            route.last_seen = route.last_seen.max(last_seen);
+        }
+
+        let mut routes: Vec<ObservedRoute> = routes.into_values().collect();
+        routes.sort_by(|a, b| {
+            b.last_seen
+                .cmp(&a.last_seen)
+                .then_with(|| a.hops.cmp(&b.hops))
+        });
+        routes
+    }
+}
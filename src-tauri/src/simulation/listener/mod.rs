@@ -1,14 +1,24 @@
+pub mod flood_analyzer;
+pub mod journey_tracker;
+pub mod sequence_diagram;
+pub mod session_tracker;
+pub mod shortcuts;
+
+use crate::simulation::events::NodeKind;
+use crate::simulation::listener::shortcuts::{validate_shortcut, ShortcutFailure};
+use crate::simulation::sniffer::CapturedPacket;
 use crate::simulation::state::SimulationState;
-use crate::utils::ControllerEvent;
+use crate::simulation::topology::NodeMetadata;
+use crate::utils::{now_millis, ControllerEvent};
 use common_utils::HostEvent;
-use log::{debug, error, info};
+use log::{debug, info, warn};
 use parking_lot::Mutex;
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 use wg_2024::controller::DroneEvent;
 use wg_2024::network::NodeId;
-use wg_2024::packet::{Packet, PacketType};
+use wg_2024::packet::Packet;
 
 pub struct Listener {
     state: Arc<Mutex<SimulationState>>,
@@ -28,6 +38,7 @@ impl Listener {
                 debug!("Received event: {:?}", event);
                 self.process_event(event);
             }
+            self.collect_captures();
 
             thread::sleep(Duration::from_millis(10));
         });
@@ -61,11 +72,49 @@ impl Listener {
         events
     }
 
+    /// Moves the packets seen by the sniffer to the capture store and to the event log.
+    fn collect_captures(&self) {
+        let mut sim_state = self.state.lock();
+        let pending = sim_state.get_sniffer().drain_pending();
+        if pending.is_empty() {
+            return;
+        }
+
+        let now = now_millis();
+        let sim_now = sim_state.get_clock().now_ms();
+        let run_id = sim_state.get_run_id();
+        for (node_id, seen_at, packet) in pending {
+            // The packets are collected in batches: date them back to their delivery
+            let age = seen_at.elapsed().as_millis() as u64;
+            let (timestamp, sim_time_ms) = (now.saturating_sub(age), sim_now.saturating_sub(age));
+            let node_type = match sim_state.get_graph().get_node_type(node_id) {
+                Some(NodeMetadata::Drone(_)) => NodeKind::Drone,
+                _ => NodeKind::Host,
+            };
+
+            sim_state.get_event_log_mut().push_delivery(
+                (node_id, node_type),
+                &packet,
+                (timestamp, sim_time_ms),
+            );
+            sim_state.get_sniffer_mut().record(CapturedPacket {
+                run_id,
+                node: node_id,
+                timestamp,
+                sim_time_ms,
+                packet,
+            });
+        }
+    }
+
     /// Processes the event by updating the metrics or forwarding the packet if it's a shortcut.
     fn process_event(&self, event: ControllerEvent) {
         {
             let mut sim_state = self.state.lock();
-            sim_state.get_received_messages_mut().push(event.clone());
+            let sim_time_ms = sim_state.get_clock().now_ms();
+            sim_state
+                .get_event_log_mut()
+                .push(&event, now_millis(), sim_time_ms);
         }
 
         match event {
@@ -81,92 +130,125 @@ impl Listener {
     /// Handles the events coming from the drones updating the metrics or forwarding the packet if it's a shortcut.
     fn handle_drone_event(&self, node_id: NodeId, event: DroneEvent) {
         let mut sim_state = self.state.lock();
+        let now = sim_state.get_clock().now_ms();
         match event {
             DroneEvent::PacketSent(packet) => {
                 sim_state
                     .get_metrics_mut()
-                    .update_drone_packet_sent(node_id, &packet);
+                    .update_drone_packet_sent(node_id, &packet, now);
+                sim_state
+                    .get_flood_analyzer_mut()
+                    .record_packet(node_id, &packet, now);
+                sim_state
+                    .get_session_tracker_mut()
+                    .record_sent(node_id, &packet, now);
+                sim_state
+                    .get_journey_tracker_mut()
+                    .record_sent(node_id, &packet, now);
             }
             DroneEvent::PacketDropped(packet) => {
                 sim_state
                     .get_metrics_mut()
-                    .update_drone_packet_dropped(node_id, &packet);
+                    .update_drone_packet_dropped(node_id, &packet, now);
+                sim_state
+                    .get_session_tracker_mut()
+                    .record_dropped(node_id, &packet, now);
+                sim_state
+                    .get_journey_tracker_mut()
+                    .record_dropped(node_id, &packet, now);
+            }
+            DroneEvent::ControllerShortcut(packet) => {
+                sim_state
+                    .get_metrics_mut()
+                    .update_drone_shortcut(node_id, &packet);
+                sim_state
+                    .get_flood_analyzer_mut()
+                    .record_packet(node_id, &packet, now);
+                drop(sim_state);
+                self.handle_shortcut(node_id, NodeKind::Drone, packet);
             }
-            DroneEvent::ControllerShortcut(packet) => match packet.pack_type {
-                PacketType::Ack(_) | PacketType::Nack(_) | PacketType::FloodResponse(_) => {
-                    sim_state
-                        .get_metrics_mut()
-                        .update_drone_packet_sent(node_id, &packet);
-                    if let Some(drone_metric) =
-                        sim_state.get_metrics_mut().drone_metrics.get_mut(&node_id)
-                    {
-                        drone_metric.record_shortcut();
-                    }
-                    drop(sim_state);
-                    self.send_packet_to_destination(packet);
-                }
-                _ => {
-                    error!(
-                        "[LISTENER] - [DRONE {}] Unexpected packet received: {:?}",
-                        node_id, packet
-                    );
-                }
-            },
         }
     }
 
     /// Handles the events coming from the hosts updating the metrics or forwarding the packet if it's a shortcut.
     fn handle_host_event(&self, node_id: NodeId, event: HostEvent) {
         let mut state = self.state.lock();
+        let now = state.get_clock().now_ms();
         match event {
             HostEvent::HostMessageSent(destination, _message, duration) => {
-                state
-                    .get_metrics_mut()
-                    .update_host_message_sent(node_id, destination, duration);
+                state.get_metrics_mut().update_host_message_sent(
+                    node_id,
+                    destination,
+                    duration,
+                    now,
+                );
             }
             HostEvent::PacketSent(packet_header) => {
                 state
                     .get_metrics_mut()
-                    .update_host_packet_sent(node_id, packet_header);
+                    .update_host_packet_sent(node_id, packet_header, now);
             }
-            HostEvent::ControllerShortcut(packet) => match packet.pack_type {
-                PacketType::Ack(_) | PacketType::Nack(_) | PacketType::FloodResponse(_) => {
-                    if let Some(host_metric) = state.get_metrics_mut().host_metrics.get_mut(&node_id) {
-                        host_metric.record_shortcut();
-                    }
-                    self.send_packet_to_destination(packet);
-                }
-                _ => {
-                    error!(
-                        "[LISTENER] - [HOST {}] Unexpected packet received: {:?}",
-                        node_id, packet
-                    );
+            HostEvent::ControllerShortcut(packet) => {
+                if let Some(host_metric) = state.get_metrics_mut().host_metrics.get_mut(&node_id) {
+                    host_metric.record_shortcut();
                 }
-            },
+                drop(state);
+                self.handle_shortcut(node_id, NodeKind::Host, packet);
+            }
         }
     }
 
-    /// Sends the packet to the destination node.
-    fn send_packet_to_destination(&self, packet: Packet) {
-        if let Some(dest_id) = packet.routing_header.destination() {
-            let state = self.state.lock();
-            if let Some((sender, _)) = state.get_inter_node_channels().get(&dest_id) {
-                if let Err(err) = sender.send(packet) {
-                    error!(
-                        "[LISTENER] Failed to send packet to destination {}: {:?}",
-                        dest_id, err
-                    );
-                } else {
-                    debug!("[LISTENER] Packet sent to destination {}", dest_id);
-                }
-            } else {
-                error!(
-                    "[LISTENER] Destination {} not found in inter_node_channels",
-                    dest_id
-                );
-            }
-        } else {
-            error!("[LISTENER] No destination found in packet routing header");
+    /// Delivers a packet sent through the simulation controller, reporting the packets that
+    /// are rejected or cannot be delivered as `ShortcutFailed` events.
+    fn handle_shortcut(&self, node_id: NodeId, sender: NodeKind, packet: Packet) {
+        let result = validate_shortcut(node_id, sender, &packet)
+            .and_then(|destination| self.deliver_shortcut(destination, &packet));
+
+        let mut state = self.state.lock();
+        let now = state.get_clock().now_ms();
+        state
+            .get_metrics_mut()
+            .update_shortcut_delivery(&packet, result, now);
+        state
+            .get_session_tracker_mut()
+            .record_shortcut(node_id, &packet, result.is_ok(), now);
+        state
+            .get_journey_tracker_mut()
+            .record_shortcut(node_id, &packet, result.is_ok(), now);
+
+        if let Err(failure) = result {
+            warn!(
+                "[LISTENER] Shortcut from {} not delivered ({:?}): {:?}",
+                node_id, failure, packet
+            );
+            state.get_event_log_mut().push_shortcut_failure(
+                (node_id, sender),
+                &packet,
+                failure,
+                (now_millis(), now),
+            );
         }
     }
+
+    /// Sends the packet to the inbound channel of `destination`. A missing channel does not
+    /// come back, so the failure is reported at once rather than retried.
+    fn deliver_shortcut(
+        &self,
+        destination: NodeId,
+        packet: &Packet,
+    ) -> Result<(), ShortcutFailure> {
+        let sender = self
+            .state
+            .lock()
+            .get_inter_node_channels()
+            .get(&destination)
+            .map(|(sender, _)| sender.clone())
+            .ok_or(ShortcutFailure::DestinationUnreachable)?;
+
+        sender
+            .send(packet.clone())
+            .map_err(|_| ShortcutFailure::ChannelDisconnected)?;
+        debug!("[LISTENER] Packet sent to destination {}", destination);
+        Ok(())
+    }
 }
@@ -0,0 +1,203 @@
+use crate::simulation::listener::journey_tracker::{HopAction, JourneyHop, PacketJourney};
+use crate::simulation::metrics::PacketTypeLabel;
+use crate::simulation::topology::{GraphState, NodeMetadata};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use wg_2024::network::NodeId;
+
+/// Name of the participant standing for the simulation controller.
+const CONTROLLER: &str = "SC";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DiagramFormat {
+    #[default]
+    Mermaid,
+    PlantUml,
+}
+
+/// A single line of the diagram, independent of the output format.
+enum Step {
+    Arrow {
+        from: String,
+        to: String,
+        label: String,
+        shortcut: bool,
+    },
+    Note {
+        over: String,
+        text: String,
+    },
+}
+
+/// Renders the journeys of a session as a sequence diagram.
+///
+/// The participants are the nodes on the paths, in route order, followed by the simulation
+/// controller if a packet was delivered through it. The steps follow the order in which the
+/// hops were recorded by the listener.
+pub fn render(
+    format: DiagramFormat,
+    (source, session_id): (NodeId, u64),
+    journeys: &[&PacketJourney],
+    graph: &GraphState,
+) -> String {
+    let mut participants: Vec<NodeId> = Vec::new();
+    for journey in journeys {
+        let nodes = journey
+            .route
+            .iter()
+            .chain(journey.hops.iter().map(|h| &h.node));
+        for &node in nodes {
+            if !participants.contains(&node) {
+                participants.push(node);
+            }
+        }
+    }
+    let uses_controller = journeys.iter().any(|j| j.shortcut_used);
+
+    let mut hops: Vec<(&PacketJourney, &JourneyHop)> = journeys
+        .iter()
+        .flat_map(|j| j.hops.iter().map(move |hop| (*j, hop)))
+        .collect();
+    hops.sort_by_key(|(_, hop)| hop.seq);
+
+    let mut steps = Vec::new();
+    for (journey, hop) in hops {
+        let label = label_of(journey);
+        match hop.action {
+            HopAction::Forwarded { to } => steps.push(Step::Arrow {
+                from: participant_id(hop.node),
+                to: participant_id(to),
+                label,
+                shortcut: false,
+            }),
+            HopAction::Dropped => steps.push(Step::Note {
+                over: participant_id(hop.node),
+                text: format!("{} dropped", label),
+            }),
+            HopAction::ShortcutFailed => steps.push(Step::Note {
+                over: participant_id(hop.node),
+                text: format!("{} lost by the controller", label),
+            }),
+            HopAction::Shortcut => {
+                steps.push(Step::Arrow {
+                    from: participant_id(hop.node),
+                    to: CONTROLLER.to_string(),
+                    label: label.clone(),
+                    shortcut: true,
+                });
+                if let Some(&destination) = journey.route.last() {
+                    steps.push(Step::Arrow {
+                        from: CONTROLLER.to_string(),
+                        to: participant_id(destination),
+                        label,
+                        shortcut: true,
+                    });
+                }
+            }
+        }
+    }
+
+    let title = format!(
+        "Session {} from {}",
+        session_id,
+        participant_name(source, graph)
+    );
+    let mut out = String::new();
+    match format {
+        DiagramFormat::Mermaid => {
+            let _ = writeln!(out, "sequenceDiagram");
+            let _ = writeln!(out, "    title {}", title);
+            for &node in &participants {
+                let _ = writeln!(
+                    out,
+                    "    participant {} as {}",
+                    participant_id(node),
+                    participant_name(node, graph)
+                );
+            }
+            if uses_controller {
+                let _ = writeln!(
+                    out,
+                    "    participant {} as Simulation Controller",
+                    CONTROLLER
+                );
+            }
+            for step in steps {
+                let _ = match step {
+                    Step::Arrow {
+                        from,
+                        to,
+                        label,
+                        shortcut,
+                    } => {
+                        let arrow = if shortcut { "-->>" } else { "->>" };
+                        writeln!(out, "    {}{}{}: {}", from, arrow, to, label)
+                    }
+                    Step::Note { over, text } => writeln!(out, "    Note over {}: {}", over, text),
+                };
+            }
+        }
+        DiagramFormat::PlantUml => {
+            let _ = writeln!(out, "@startuml");
+            let _ = writeln!(out, "title {}", title);
+            for &node in &participants {
+                let _ = writeln!(
+                    out,
+                    "participant \"{}\" as {}",
+                    participant_name(node, graph),
+                    participant_id(node)
+                );
+            }
+            if uses_controller {
+                let _ = writeln!(
+                    out,
+                    "participant \"Simulation Controller\" as {}",
+                    CONTROLLER
+                );
+            }
+            for step in steps {
+                let _ = match step {
+                    Step::Arrow {
+                        from,
+                        to,
+                        label,
+                        shortcut,
+                    } => {
+                        let arrow = if shortcut { "-->" } else { "->" };
+                        writeln!(out, "{} {} {}: {}", from, arrow, to, label)
+                    }
+                    Step::Note { over, text } => writeln!(out, "note over {}: {}", over, text),
+                };
+            }
+            let _ = writeln!(out, "@enduml");
+        }
+    }
+    out
+}
+
+fn label_of(journey: &PacketJourney) -> String {
+    let index = journey.key.index;
+    match journey.key.packet_type {
+        PacketTypeLabel::MsgFragment => format!("Fragment {}", index),
+        PacketTypeLabel::Ack => format!("Ack {}", index),
+        PacketTypeLabel::Nack => match journey.nack_type {
+            Some(nack_type) => format!("Nack {} ({:?})", index, nack_type),
+            None => format!("Nack {}", index),
+        },
+        PacketTypeLabel::FloodRequest => format!("FloodRequest {}", index),
+        PacketTypeLabel::FloodResponse => format!("FloodResponse {}", index),
+    }
+}
+
+fn participant_id(node: NodeId) -> String {
+    format!("N{}", node)
+}
+
+fn participant_name(node: NodeId, graph: &GraphState) -> String {
+    match graph.get_node_type(node) {
+        Some(NodeMetadata::Drone(_)) => format!("Drone {}", node),
+        Some(NodeMetadata::Client) => format!("Client {}", node),
+        Some(NodeMetadata::Server) => format!("Server {}", node),
+        None => format!("Node {}", node),
+    }
+}
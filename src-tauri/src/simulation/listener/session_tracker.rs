@@ -0,0 +1,275 @@
+use crate::simulation::metrics::edges::next_hop_of;
+use crate::simulation::metrics::NackTypeLabel;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use wg_2024::network::NodeId;
+use wg_2024::packet::{Nack, Packet, PacketType};
+
+/// Maximum number of sessions kept by the tracker. The oldest session is evicted first.
+const MAX_SESSIONS: usize = 10_000;
+
+/// Time without activity after which a session that is not fully acked is considered over.
+const SESSION_TIMEOUT_MS: u64 = 10_000;
+
+/// Follows every fragment of each `(source, session_id)` from the first hop to the final Ack.
+#[derive(Debug, Default)]
+pub struct SessionTracker {
+    sessions: HashMap<(NodeId, u64), SessionRecord>,
+    /// Keys of the sessions, in the order they were first seen
+    order: VecDeque<(NodeId, u64)>,
+}
+
+#[derive(Debug, Default)]
+struct SessionRecord {
+    destination: Option<NodeId>,
+    total_fragments: u64,
+    first_seen: u64,
+    last_activity: u64,
+    fragments: BTreeMap<u64, FragmentRecord>,
+}
+
+/// The delivery state of a single fragment.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FragmentRecord {
+    pub fragment_index: u64,
+    /// Number of times the fragment reached the first hop
+    pub transmissions: u64,
+    pub drops: u64,
+    pub nacks: HashMap<NackTypeLabel, u64>,
+    /// Run-relative timestamp in milliseconds at which the last drone sent the fragment to the destination
+    pub reached_destination_at: Option<u64>,
+    /// Run-relative timestamp in milliseconds at which the Ack reached the source
+    pub acked_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionStatus {
+    InProgress,
+    Delivered,
+    PartiallyDelivered,
+    Lost,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub source: NodeId,
+    pub session_id: u64,
+    pub destination: Option<NodeId>,
+    pub status: SessionStatus,
+    pub total_fragments: u64,
+    pub acked_fragments: u64,
+    pub retransmissions: u64,
+    pub nacks: u64,
+    pub drops: u64,
+    /// Run-relative timestamps in milliseconds
+    pub first_seen: u64,
+    pub last_activity: u64,
+    pub fragments: Vec<FragmentRecord>,
+}
+
+/// Filters accepted by `get_sessions`. Unset fields match every session.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionFilter {
+    pub source: Option<NodeId>,
+    pub destination: Option<NodeId>,
+    pub session_id: Option<u64>,
+    pub status: Option<SessionStatus>,
+    /// Maximum number of sessions returned, most recent first
+    pub limit: Option<usize>,
+}
+
+impl SessionTracker {
+    /// Records a packet sent (or sent through the controller) by the drone `node_id`.
+    pub fn record_sent(&mut self, node_id: NodeId, packet: &Packet, now_ms: u64) {
+        let header = &packet.routing_header;
+        match &packet.pack_type {
+            PacketType::MsgFragment(fragment) => {
+                let Some(source) = header.source() else {
+                    return;
+                };
+                let destination = header.destination();
+                let session = self.session_for((source, packet.session_id), now_ms);
+                session.destination = destination;
+                session.total_fragments = fragment.total_n_fragments;
+
+                let record = session.fragment(fragment.fragment_index);
+                if header.hops.get(1) == Some(&node_id) {
+                    record.transmissions += 1;
+                }
+                if destination.is_some() && next_hop_of(header, node_id) == destination {
+                    record.reached_destination_at.get_or_insert(now_ms);
+                }
+            }
+            PacketType::Ack(ack) => {
+                // Acks travel back to the source of the session
+                let Some(source) = header.destination() else {
+                    return;
+                };
+                if next_hop_of(header, node_id) == Some(source) {
+                    self.record_ack(source, packet.session_id, ack.fragment_index, now_ms);
+                }
+            }
+            PacketType::Nack(nack) => self.record_nack(node_id, packet, nack, now_ms),
+            _ => {}
+        }
+    }
+
+    /// Records a packet sent through the simulation controller by the node `node_id`.
+    /// Acks are only recorded once `delivered` to the source.
+    pub fn record_shortcut(
+        &mut self,
+        node_id: NodeId,
+        packet: &Packet,
+        delivered: bool,
+        now_ms: u64,
+    ) {
+        match &packet.pack_type {
+            PacketType::Ack(ack) if delivered => {
+                if let Some(source) = packet.routing_header.destination() {
+                    self.record_ack(source, packet.session_id, ack.fragment_index, now_ms);
+                }
+            }
+            PacketType::Nack(nack) => self.record_nack(node_id, packet, nack, now_ms),
+            _ => {}
+        }
+    }
+
+    fn record_nack(&mut self, node_id: NodeId, packet: &Packet, nack: &Nack, now_ms: u64) {
+        let header = &packet.routing_header;
+        let Some(source) = header.destination() else {
+            return;
+        };
+        // NACKs are counted once, when they leave the drone that originated them
+        if header.source() == Some(node_id) {
+            let session = self.session_for((source, packet.session_id), now_ms);
+            *session
+                .fragment(nack.fragment_index)
+                .nacks
+                .entry(NackTypeLabel::from(&nack.nack_type))
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// Records a packet dropped by the drone `node_id`.
+    pub fn record_dropped(&mut self, node_id: NodeId, packet: &Packet, now_ms: u64) {
+        let header = &packet.routing_header;
+        if let PacketType::MsgFragment(fragment) = &packet.pack_type {
+            let Some(source) = header.source() else {
+                return;
+            };
+            let session = self.session_for((source, packet.session_id), now_ms);
+            session.destination = header.destination();
+            session.total_fragments = fragment.total_n_fragments;
+
+            let record = session.fragment(fragment.fragment_index);
+            record.drops += 1;
+            if header.hops.get(1) == Some(&node_id) {
+                record.transmissions += 1;
+            }
+        }
+    }
+
+    fn record_ack(&mut self, source: NodeId, session_id: u64, fragment_index: u64, now_ms: u64) {
+        let session = self.session_for((source, session_id), now_ms);
+        session
+            .fragment(fragment_index)
+            .acked_at
+            .get_or_insert(now_ms);
+    }
+
+    fn session_for(&mut self, key: (NodeId, u64), now_ms: u64) -> &mut SessionRecord {
+        if !self.sessions.contains_key(&key) {
+            self.order.push_back(key);
+            if self.order.len() > MAX_SESSIONS {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.sessions.remove(&oldest);
+                }
+            }
+        }
+
+        let session = self.sessions.entry(key).or_insert_with(|| SessionRecord {
+            first_seen: now_ms,
+            ..Default::default()
+        });
+        session.last_activity = now_ms;
+        session
+    }
+
+    /// Returns the sessions matching `filter`, most recent first.
+    pub fn sessions(&self, filter: &SessionFilter, now_ms: u64) -> Vec<SessionSummary> {
+        self.order
+            .iter()
+            .rev()
+            .filter(|(source, session_id)| {
+                filter.source.is_none_or(|s| s == *source)
+                    && filter.session_id.is_none_or(|id| id == *session_id)
+            })
+            .filter_map(|key| {
+                self.sessions
+                    .get(key)
+                    .map(|session| session.summary(*key, now_ms))
+            })
+            .filter(|summary| {
+                filter
+                    .destination
+                    .is_none_or(|d| summary.destination == Some(d))
+                    && filter.status.is_none_or(|s| s == summary.status)
+            })
+            .take(filter.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+impl SessionRecord {
+    fn fragment(&mut self, fragment_index: u64) -> &mut FragmentRecord {
+        self.fragments
+            .entry(fragment_index)
+            .or_insert_with(|| FragmentRecord {
+                fragment_index,
+                ..Default::default()
+            })
+    }
+
+    fn summary(&self, (source, session_id): (NodeId, u64), now_ms: u64) -> SessionSummary {
+        let total_fragments = self.total_fragments.max(self.fragments.len() as u64);
+        let acked_fragments = self
+            .fragments
+            .values()
+            .filter(|f| f.acked_at.is_some())
+            .count() as u64;
+        let stale = now_ms.saturating_sub(self.last_activity) > SESSION_TIMEOUT_MS;
+
+        let status = if total_fragments > 0 && acked_fragments == total_fragments {
+            SessionStatus::Delivered
+        } else if !stale {
+            SessionStatus::InProgress
+        } else if acked_fragments > 0 {
+            SessionStatus::PartiallyDelivered
+        } else {
+            SessionStatus::Lost
+        };
+
+        SessionSummary {
+            source,
+            session_id,
+            destination: self.destination,
+            status,
+            total_fragments,
+            acked_fragments,
+            retransmissions: self
+                .fragments
+                .values()
+                .map(|f| f.transmissions.saturating_sub(1))
+                .sum(),
+            nacks: self
+                .fragments
+                .values()
+                .map(|f| f.nacks.values().sum::<u64>())
+                .sum(),
+            drops: self.fragments.values().map(|f| f.drops).sum(),
+            first_seen: self.first_seen,
+            last_activity: self.last_activity,
+            fragments: self.fragments.values().cloned().collect(),
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::simulation::events::NodeKind;
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+use wg_2024::packet::{Packet, PacketType};
+
+/// Why a packet sent through the simulation controller was not delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ShortcutFailure {
+    /// Only Acks, NACKs and flood responses can be sent through the controller
+    InvalidPacketType,
+    /// The sender is not on the route, is its destination, is a host that is not its source,
+    /// or is a drone claiming to originate an Ack or a flood response
+    InvalidSenderPosition,
+    /// The routing header has no destination
+    NoDestination,
+    /// The destination has no inbound channel, e.g. because it crashed
+    DestinationUnreachable,
+    /// The inbound channel of the destination is disconnected
+    ChannelDisconnected,
+}
+
+impl ShortcutFailure {
+    /// Whether the packet was rejected by the listener, rather than lost while delivering it.
+    pub fn is_rejection(self) -> bool {
+        matches!(
+            self,
+            ShortcutFailure::InvalidPacketType
+                | ShortcutFailure::InvalidSenderPosition
+                | ShortcutFailure::NoDestination
+        )
+    }
+}
+
+/// Checks that `node_id` can send `packet` through the simulation controller, returning
+/// the destination of the packet.
+///
+/// A drone can use the shortcut for the packets it is forwarding, as long as it is not the
+/// destination, and for the NACKs it originates. A host can only use it for the packets it
+/// originates.
+pub fn validate_shortcut(
+    node_id: NodeId,
+    sender: NodeKind,
+    packet: &Packet,
+) -> Result<NodeId, ShortcutFailure> {
+    if !matches!(
+        packet.pack_type,
+        PacketType::Ack(_) | PacketType::Nack(_) | PacketType::FloodResponse(_)
+    ) {
+        return Err(ShortcutFailure::InvalidPacketType);
+    }
+
+    let header = &packet.routing_header;
+    let destination = header.destination().ok_or(ShortcutFailure::NoDestination)?;
+    let position = header
+        .hops
+        .iter()
+        .position(|&hop| hop == node_id)
+        .ok_or(ShortcutFailure::InvalidSenderPosition)?;
+
+    let valid_position = match sender {
+        NodeKind::Drone => {
+            position + 1 < header.hops.len()
+                && (position > 0 || matches!(packet.pack_type, PacketType::Nack(_)))
+        }
+        NodeKind::Host => position == 0 && destination != node_id,
+    };
+    if valid_position {
+        Ok(destination)
+    } else {
+        Err(ShortcutFailure::InvalidSenderPosition)
+    }
+}
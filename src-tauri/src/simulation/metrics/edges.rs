@@ -0,0 +1,117 @@
+use crate::simulation::metrics::time_series::{Resolution, SeriesPoint, TimePoint, TimeSeries};
+use crate::simulation::metrics::PacketTypeLabel;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+
+/// Counters of a directed edge `source -> destination`.
+#[derive(Debug, Clone, Default)]
+pub struct EdgeMetrics {
+    /// Count for each packet type sent over the edge
+    pub packet_type_counts: HashMap<PacketTypeLabel, u64>,
+    /// Packets dropped by the source instead of being sent over the edge
+    pub drops: u64,
+    /// Packets seen by the destination after being sent over the edge
+    pub arrivals: u64,
+    /// Time series for the number of packets sent and dropped
+    pub time_series: TimeSeries<EdgeTimePoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeTimePoint {
+    /// Run-relative timestamp in milliseconds
+    pub timestamp: u64,
+    pub packets: u64,
+    pub drops: u64,
+}
+
+impl TimePoint for EdgeTimePoint {
+    fn set_timestamp(&mut self, timestamp: u64) {
+        self.timestamp = timestamp;
+    }
+}
+
+/// Edge metrics as exposed to the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeStats {
+    pub source: NodeId,
+    pub destination: NodeId,
+    pub packets_sent: u64,
+    pub packet_type_counts: HashMap<PacketTypeLabel, u64>,
+    pub drops: u64,
+    pub drop_rate: f32,
+    /// Packets sent over the edge and not yet seen by the destination. Only available
+    /// when the destination is a drone, since hosts do not report received packets.
+    pub in_flight: Option<u64>,
+    pub utilization: Vec<SeriesPoint<EdgeTimePoint>>,
+}
+
+impl EdgeMetrics {
+    pub fn number_of_packets_sent(&self) -> u64 {
+        self.packet_type_counts.values().sum()
+    }
+
+    pub fn record_packet(&mut self, now_ms: u64, packet_type: PacketTypeLabel) {
+        *self.packet_type_counts.entry(packet_type).or_insert(0) += 1;
+        self.update_time_series(now_ms);
+    }
+
+    pub fn record_drop(&mut self, now_ms: u64) {
+        self.drops += 1;
+        self.update_time_series(now_ms);
+    }
+
+    pub fn record_arrival(&mut self) {
+        self.arrivals += 1;
+    }
+
+    fn update_time_series(&mut self, now_ms: u64) {
+        let point = EdgeTimePoint {
+            timestamp: now_ms,
+            packets: self.number_of_packets_sent(),
+            drops: self.drops,
+        };
+        self.time_series.record(now_ms, point);
+    }
+
+    pub fn stats(
+        &self,
+        (source, destination): (NodeId, NodeId),
+        destination_is_drone: bool,
+        resolution: Resolution,
+    ) -> EdgeStats {
+        let packets_sent = self.number_of_packets_sent();
+        let fragments = *self
+            .packet_type_counts
+            .get(&PacketTypeLabel::MsgFragment)
+            .unwrap_or(&0);
+        EdgeStats {
+            source,
+            destination,
+            packets_sent,
+            packet_type_counts: self.packet_type_counts.clone(),
+            drops: self.drops,
+            drop_rate: if fragments + self.drops == 0 {
+                0.0
+            } else {
+                self.drops as f32 / (fragments + self.drops) as f32
+            },
+            in_flight: destination_is_drone.then(|| packets_sent.saturating_sub(self.arrivals)),
+            utilization: self.time_series.query(None, None, resolution),
+        }
+    }
+}
+
+/// Returns the node following `node` in the route, regardless of the hop index.
+pub fn next_hop_of(routing_header: &SourceRoutingHeader, node: NodeId) -> Option<NodeId> {
+    let position = routing_header.hops.iter().position(|&hop| hop == node)?;
+    routing_header.hops.get(position + 1).copied()
+}
+
+/// Returns the node preceding `node` in the route, regardless of the hop index.
+pub fn previous_hop_of(routing_header: &SourceRoutingHeader, node: NodeId) -> Option<NodeId> {
+    let position = routing_header.hops.iter().position(|&hop| hop == node)?;
+    position
+        .checked_sub(1)
+        .and_then(|previous| routing_header.hops.get(previous).copied())
+}
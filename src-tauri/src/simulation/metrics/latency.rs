@@ -0,0 +1,294 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+use wg_2024::network::NodeId;
+
+/// Number of bits used for the linear part of each bucket: values below
+/// `2^SUB_BUCKET_BITS` microseconds are exact, larger ones have a relative error below 1/64.
+const SUB_BUCKET_BITS: u32 = 7;
+
+/// Length of the intervals used to answer windowed queries.
+const INTERVAL_MS: u64 = 1000;
+
+/// Number of intervals kept for windowed queries (one hour).
+const MAX_INTERVALS: usize = 3600;
+
+/// A log-linear (HDR-style) histogram of latencies, recorded in microseconds.
+#[derive(Debug, Clone, Default)]
+pub struct LatencyHistogram {
+    /// Bucket key -> number of samples
+    counts: BTreeMap<u32, u64>,
+    total: u64,
+    min: u64,
+    max: u64,
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, micros: u64) {
+        *self.counts.entry(bucket_key(micros)).or_insert(0) += 1;
+        self.min = if self.total == 0 {
+            micros
+        } else {
+            self.min.min(micros)
+        };
+        self.max = self.max.max(micros);
+        self.total += 1;
+    }
+
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        if other.total == 0 {
+            return;
+        }
+        for (&key, &count) in &other.counts {
+            *self.counts.entry(key).or_insert(0) += count;
+        }
+        self.min = if self.total == 0 {
+            other.min
+        } else {
+            self.min.min(other.min)
+        };
+        self.max = self.max.max(other.max);
+        self.total += other.total;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the value (in microseconds) below which `quantile` of the samples fall.
+    pub fn value_at_quantile(&self, quantile: f64) -> u64 {
+        if self.total == 0 {
+            return 0;
+        }
+
+        let target = ((quantile.clamp(0.0, 1.0) * self.total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (&key, &count) in &self.counts {
+            seen += count;
+            if seen >= target {
+                return highest_equivalent_value(key).clamp(self.min, self.max);
+            }
+        }
+        self.max
+    }
+
+    pub fn stats(&self) -> LatencyStats {
+        let to_ms = |micros: u64| micros as f64 / 1000.0;
+        LatencyStats {
+            count: self.total,
+            min_ms: to_ms(if self.total == 0 { 0 } else { self.min }),
+            p50_ms: to_ms(self.value_at_quantile(0.50)),
+            p90_ms: to_ms(self.value_at_quantile(0.90)),
+            p99_ms: to_ms(self.value_at_quantile(0.99)),
+            max_ms: to_ms(self.max),
+        }
+    }
+}
+
+/// Encodes a value as `(shift << 8) | mantissa`, so that keys are ordered as the values.
+fn bucket_key(value: u64) -> u32 {
+    let msb = 63 - value.max(1).leading_zeros();
+    let shift = (msb + 1).saturating_sub(SUB_BUCKET_BITS);
+    let mantissa = (value >> shift) as u32;
+    (shift << 8) | mantissa
+}
+
+fn highest_equivalent_value(key: u32) -> u64 {
+    let shift = key >> 8;
+    let mantissa = (key & 0xff) as u64;
+    ((mantissa + 1) << shift) - 1
+}
+
+/// Latency percentiles, in milliseconds.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub count: u64,
+    pub min_ms: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Latency stats of the messages sent from `source` to `destination`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteLatencyStats {
+    pub source: NodeId,
+    pub destination: NodeId,
+    #[serde(flatten)]
+    pub stats: LatencyStats,
+}
+
+/// Records the latencies of a single host pair, both over the whole run and
+/// in fixed intervals to answer windowed queries.
+#[derive(Debug, Clone, Default)]
+pub struct LatencyRecorder {
+    total: LatencyHistogram,
+    /// (interval start in ms, histogram of the interval), oldest first
+    intervals: VecDeque<(u64, LatencyHistogram)>,
+}
+
+impl LatencyRecorder {
+    pub fn record(&mut self, now_ms: u64, latency: Duration) {
+        let micros = latency.as_micros() as u64;
+        self.total.record(micros);
+
+        let interval_start = now_ms - now_ms % INTERVAL_MS;
+        if let Some((start, histogram)) = self.intervals.back_mut() {
+            if *start == interval_start {
+                histogram.record(micros);
+                return;
+            }
+        }
+
+        let mut histogram = LatencyHistogram::default();
+        histogram.record(micros);
+        self.intervals.push_back((interval_start, histogram));
+        if self.intervals.len() > MAX_INTERVALS {
+            self.intervals.pop_front();
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.total.count()
+    }
+
+    /// Returns the stats over the last `window`, or over the whole run if `window` is `None`.
+    pub fn stats(&self, now_ms: u64, window: Option<Duration>) -> LatencyStats {
+        match window {
+            None => self.total.stats(),
+            Some(window) => {
+                let since = now_ms.saturating_sub(window.as_millis() as u64);
+                let mut histogram = LatencyHistogram::default();
+                for (_, interval) in self
+                    .intervals
+                    .iter()
+                    .filter(|(start, _)| start + INTERVAL_MS > since)
+                {
+                    histogram.merge(interval);
+                }
+                histogram.stats()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_values_have_their_own_bucket() {
+        for value in 0..(1 << SUB_BUCKET_BITS) {
+            assert_eq!(highest_equivalent_value(bucket_key(value)), value);
+        }
+    }
+
+    #[test]
+    fn large_values_share_buckets_of_bounded_width() {
+        // 128 = 64 << 1: the bucket covers 128 and 129
+        assert_eq!(bucket_key(128), bucket_key(129));
+        assert_ne!(bucket_key(129), bucket_key(130));
+        assert_eq!(highest_equivalent_value(bucket_key(128)), 129);
+        // 1000 = 125 << 3: the bucket covers 1000..=1007
+        assert_eq!(highest_equivalent_value(bucket_key(1000)), 1007);
+        assert_eq!(bucket_key(1000), bucket_key(1007));
+        assert_ne!(bucket_key(1007), bucket_key(1008));
+
+        let mut value = 1 << SUB_BUCKET_BITS;
+        while value < 1 << 40 {
+            let highest = highest_equivalent_value(bucket_key(value));
+            assert!(highest >= value);
+            assert!(((highest - value) as f64) < value as f64 / 64.0);
+            value = value * 3 / 2 + 1;
+        }
+    }
+
+    #[test]
+    fn bucket_keys_are_ordered_as_the_values() {
+        let mut previous = bucket_key(0);
+        for value in 1..100_000 {
+            let key = bucket_key(value);
+            assert!(
+                key >= previous,
+                "bucket of {} is before the previous one",
+                value
+            );
+            previous = key;
+        }
+    }
+
+    #[test]
+    fn quantiles_of_exact_values() {
+        let mut histogram = LatencyHistogram::default();
+        for value in 1..=100 {
+            histogram.record(value);
+        }
+        assert_eq!(histogram.count(), 100);
+        assert_eq!(histogram.value_at_quantile(0.0), 1);
+        assert_eq!(histogram.value_at_quantile(0.5), 50);
+        assert_eq!(histogram.value_at_quantile(0.9), 90);
+        assert_eq!(histogram.value_at_quantile(0.99), 99);
+        assert_eq!(histogram.value_at_quantile(1.0), 100);
+    }
+
+    #[test]
+    fn quantiles_are_clamped_to_the_recorded_range() {
+        let mut histogram = LatencyHistogram::default();
+        histogram.record(1000);
+        assert_eq!(histogram.value_at_quantile(0.5), 1000);
+        assert_eq!(histogram.value_at_quantile(1.0), 1000);
+    }
+
+    #[test]
+    fn empty_histogram_reports_zeros() {
+        let stats = LatencyHistogram::default().stats();
+        assert_eq!(stats.count, 0);
+        assert_eq!(stats.min_ms, 0.0);
+        assert_eq!(stats.p99_ms, 0.0);
+        assert_eq!(stats.max_ms, 0.0);
+    }
+
+    #[test]
+    fn merge_is_the_same_as_recording_every_value() {
+        let mut merged = LatencyHistogram::default();
+        let mut other = LatencyHistogram::default();
+        let mut all = LatencyHistogram::default();
+        for value in [5, 300, 7000] {
+            merged.record(value);
+            all.record(value);
+        }
+        for value in [2, 150, 90_000] {
+            other.record(value);
+            all.record(value);
+        }
+        merged.merge(&other);
+        merged.merge(&LatencyHistogram::default());
+
+        assert_eq!(merged.count(), all.count());
+        for quantile in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            assert_eq!(
+                merged.value_at_quantile(quantile),
+                all.value_at_quantile(quantile)
+            );
+        }
+        assert_eq!(merged.stats().min_ms, 0.002);
+        assert_eq!(merged.stats().max_ms, 90.0);
+    }
+
+    #[test]
+    fn windowed_stats_only_use_the_recent_intervals() {
+        let mut recorder = LatencyRecorder::default();
+        recorder.record(500, Duration::from_millis(10));
+        recorder.record(5_500, Duration::from_millis(20));
+
+        let recent = recorder.stats(5_600, Some(Duration::from_secs(1)));
+        assert_eq!(recent.count, 1);
+        assert_eq!(recent.p50_ms, 20.0);
+
+        let whole_run = recorder.stats(5_600, None);
+        assert_eq!(whole_run.count, 2);
+        assert_eq!(whole_run.min_ms, 10.0);
+        assert_eq!(whole_run.max_ms, 20.0);
+    }
+}
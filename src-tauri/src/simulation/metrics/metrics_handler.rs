@@ -1,10 +1,15 @@
+use crate::simulation::listener::shortcuts::ShortcutFailure;
+use crate::simulation::metrics::edges::{next_hop_of, previous_hop_of};
+use crate::simulation::metrics::latency::RouteLatencyStats;
 use crate::simulation::metrics::{
-    DroneMetrics, HostMetrics, HostMetricsTimePoint, Metrics, MetricsTimePoint, PacketTypeLabel,
+    DroneMetrics, HostMetrics, HostMetricsTimePoint, Metrics, MetricsTimePoint, NackTypeLabel,
+    PacketTypeLabel,
 };
 use common_utils::PacketTypeHeader;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use wg_2024::network::NodeId;
-use wg_2024::packet::{NodeType, Packet};
+use std::collections::HashMap;
+use std::time::Duration;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{NackType, NodeType, Packet, PacketType};
 
 impl Metrics {
     pub fn insert_node(&mut self, node_id: NodeId, node_type: NodeType) {
@@ -18,11 +23,76 @@ impl Metrics {
         }
     }
 
+    /// Forget the metrics of a node that never took part in the simulation.
+    pub fn remove_node(&mut self, node_id: NodeId) {
+        self.drone_metrics.remove(&node_id);
+        self.host_metrics.remove(&node_id);
+    }
+
     /// Update the metrics for a packet sent by a drone.
-    pub fn update_drone_packet_sent(&mut self, node_id: NodeId, packet: &Packet) {
+    pub fn update_drone_packet_sent(&mut self, node_id: NodeId, packet: &Packet, now_ms: u64) {
+        self.record_drone_packet(node_id, packet, now_ms);
+        self.update_edge_arrival(node_id, &packet.routing_header);
+        self.update_edge_packet_sent(
+            node_id,
+            &packet.routing_header,
+            PacketTypeLabel::from(&packet.pack_type),
+            now_ms,
+        );
+    }
+
+    /// Update the metrics for a packet that a drone sent through the simulation controller.
+    /// The packet is not counted as sent by the drone, since it did not leave on any edge.
+    pub fn update_drone_shortcut(&mut self, node_id: NodeId, packet: &Packet) {
+        if let Some(drone_metrics) = self.drone_metrics.get_mut(&node_id) {
+            drone_metrics.record_shortcut();
+        }
+        if let PacketType::Nack(nack) = &packet.pack_type {
+            if packet.routing_header.source() == Some(node_id) {
+                self.update_nack_originated(node_id, &nack.nack_type);
+            }
+        }
+        self.update_edge_arrival(node_id, &packet.routing_header);
+    }
+
+    /// Update the metrics for the delivery of a packet sent through the simulation controller.
+    pub fn update_shortcut_delivery(
+        &mut self,
+        packet: &Packet,
+        result: Result<(), ShortcutFailure>,
+        now_ms: u64,
+    ) {
+        let Some(destination) = packet.routing_header.destination() else {
+            return;
+        };
+        let stats = self.shortcut_deliveries.entry(destination).or_default();
+        match result {
+            Ok(()) => {
+                stats.delivered += 1;
+                if let PacketType::Ack(_) = packet.pack_type {
+                    let host_metrics = self.host_metrics.get_mut(&destination);
+                    if let (Some(metrics), Some(source)) =
+                        (host_metrics, packet.routing_header.source())
+                    {
+                        metrics.record_ack(source, now_ms);
+                    }
+                }
+            }
+            Err(failure) => *stats.failures.entry(failure).or_insert(0) += 1,
+        }
+    }
+
+    fn record_drone_packet(&mut self, node_id: NodeId, packet: &Packet, now_ms: u64) {
         let packet_type = PacketTypeLabel::from(&packet.pack_type);
         if let Some(drone_metrics) = self.drone_metrics.get_mut(&node_id) {
-            drone_metrics.record_packet(packet_type);
+            drone_metrics.record_packet(packet_type, now_ms);
+        }
+
+        // NACKs are counted once, when they leave the drone that originated them
+        if let PacketType::Nack(nack) = &packet.pack_type {
+            if packet.routing_header.source() == Some(node_id) {
+                self.update_nack_originated(node_id, &nack.nack_type);
+            }
         }
 
         // Update the global heatmap
@@ -34,7 +104,7 @@ impl Metrics {
             if let Some(destination) = packet.routing_header.destination() {
                 if let Some(metrics) = self.host_metrics.get_mut(&destination) {
                     if let Some(source) = packet.routing_header.source() {
-                        metrics.record_ack(source);
+                        metrics.record_ack(source, now_ms);
                     }
                 }
             }
@@ -42,10 +112,18 @@ impl Metrics {
     }
 
     /// Update the metrics for a packet dropped by a drone.
-    pub fn update_drone_packet_dropped(&mut self, node_id: NodeId, _packet: &Packet) {
+    pub fn update_drone_packet_dropped(&mut self, node_id: NodeId, packet: &Packet, now_ms: u64) {
         if let Some(drone_metrics) = self.drone_metrics.get_mut(&node_id) {
             drone_metrics.drops += 1;
-            drone_metrics.update_pdr(false);
+            drone_metrics.update_pdr(false, now_ms);
+        }
+
+        self.update_edge_arrival(node_id, &packet.routing_header);
+        if let Some(next_hop) = next_hop_of(&packet.routing_header, node_id) {
+            self.edge_metrics
+                .entry((node_id, next_hop))
+                .or_default()
+                .record_drop(now_ms);
         }
     }
 
@@ -53,24 +131,57 @@ impl Metrics {
     pub fn update_host_message_sent(
         &mut self,
         node_id: NodeId,
-        _destination: NodeId,
+        destination: NodeId,
         latency: Duration,
+        now_ms: u64,
     ) {
         if let Some(host_metrics) = self.host_metrics.get_mut(&node_id) {
-            host_metrics.record_latency(latency);
+            host_metrics.record_latency(destination, latency, now_ms);
         }
     }
 
+    /// Returns the latency stats of every host pair matching the given source and destination.
+    /// If `window` is set, only the latencies recorded in the last `window` before `now_ms`
+    /// are considered.
+    pub fn route_latency_stats(
+        &self,
+        source: Option<NodeId>,
+        destination: Option<NodeId>,
+        window: Option<Duration>,
+        now_ms: u64,
+    ) -> Vec<RouteLatencyStats> {
+        let mut stats: Vec<RouteLatencyStats> = self
+            .host_metrics
+            .iter()
+            .filter(|(&host, _)| source.is_none_or(|s| s == host))
+            .flat_map(|(&host, host_metrics)| {
+                host_metrics
+                    .route_latencies
+                    .iter()
+                    .filter(move |(&dest, _)| destination.is_none_or(|d| d == dest))
+                    .map(move |(&dest, recorder)| RouteLatencyStats {
+                        source: host,
+                        destination: dest,
+                        stats: recorder.stats(now_ms, window),
+                    })
+            })
+            .collect();
+
+        stats.sort_by_key(|s| (s.source, s.destination));
+        stats
+    }
+
     /// Update the metrics for a packet sent by a host
     pub fn update_host_packet_sent(
         &mut self,
         node_id: NodeId,
         packet_header: common_utils::PacketHeader,
+        now_ms: u64,
     ) {
         if let Some(host_metrics) = self.host_metrics.get_mut(&node_id) {
             if let Some(destination) = packet_header.routing_header.destination() {
                 let packet_type = PacketTypeLabel::from(&packet_header.pack_type);
-                host_metrics.record_packet(destination, packet_type);
+                host_metrics.record_packet(destination, packet_type, now_ms);
             }
         }
 
@@ -79,6 +190,70 @@ impl Metrics {
                 self.update_global_heatmap(node_id, current_hop);
             }
         }
+
+        self.update_edge_packet_sent(
+            node_id,
+            &packet_header.routing_header,
+            PacketTypeLabel::from(&packet_header.pack_type),
+            now_ms,
+        );
+    }
+
+    /// Update the metrics of the edge `node_id -> next hop` for a packet sent by `node_id`.
+    /// Flood requests are not routed, so they are not attributed to any edge.
+    fn update_edge_packet_sent(
+        &mut self,
+        node_id: NodeId,
+        routing_header: &SourceRoutingHeader,
+        packet_type: PacketTypeLabel,
+        now_ms: u64,
+    ) {
+        if let Some(next_hop) = next_hop_of(routing_header, node_id) {
+            self.edge_metrics
+                .entry((node_id, next_hop))
+                .or_default()
+                .record_packet(now_ms, packet_type);
+        }
+    }
+
+    /// Update the metrics of the edge `previous hop -> node_id` for a packet seen by `node_id`.
+    fn update_edge_arrival(&mut self, node_id: NodeId, routing_header: &SourceRoutingHeader) {
+        if let Some(previous_hop) = previous_hop_of(routing_header, node_id) {
+            self.edge_metrics
+                .entry((previous_hop, node_id))
+                .or_default()
+                .record_arrival();
+        }
+    }
+
+    /// Update the NACK breakdown for a NACK originated by a drone.
+    fn update_nack_originated(&mut self, node_id: NodeId, nack_type: &NackType) {
+        let label = NackTypeLabel::from(nack_type);
+        if let Some(drone_metrics) = self.drone_metrics.get_mut(&node_id) {
+            *drone_metrics.nack_type_counts.entry(label).or_insert(0) += 1;
+        }
+
+        if let NackType::ErrorInRouting(error_node) | NackType::UnexpectedRecipient(error_node) =
+            nack_type
+        {
+            *self
+                .nack_error_nodes
+                .entry(*error_node)
+                .or_default()
+                .entry(label)
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// Returns the number of NACKs originated by the drones, by type.
+    pub fn nacks_by_type(&self) -> HashMap<NackTypeLabel, u64> {
+        let mut nacks_by_type = HashMap::new();
+        for drone_metrics in self.drone_metrics.values() {
+            for (&nack_type, &count) in &drone_metrics.nack_type_counts {
+                *nacks_by_type.entry(nack_type).or_insert(0) += count;
+            }
+        }
+        nacks_by_type
     }
 
     /// Update the global heatmap with a packet sent from `src` to `dest`.
@@ -88,36 +263,35 @@ impl Metrics {
 }
 
 impl crate::simulation::metrics::HostMetrics {
-    pub fn record_packet(&mut self, dest: NodeId, packet_type: PacketTypeLabel) {
+    pub fn record_packet(&mut self, dest: NodeId, packet_type: PacketTypeLabel, now_ms: u64) {
         let entry = self.dest_stats.entry(dest).or_insert((0, 0));
         entry.0 += 1;
         *self.packet_type_counts.entry(packet_type).or_insert(0) += 1;
 
         if packet_type == PacketTypeLabel::MsgFragment {
-            self.update_time_series();
+            self.update_time_series(now_ms);
         }
     }
 
     /// Record an ack received by the host from another host.
-    pub fn record_ack(&mut self, src: NodeId) {
+    pub fn record_ack(&mut self, src: NodeId, now_ms: u64) {
         let entry = self.dest_stats.entry(src).or_insert((0, 0));
         entry.1 += 1;
 
-        self.update_time_series();
+        self.update_time_series(now_ms);
     }
 
-    fn update_time_series(&mut self) {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map(|d| d.as_secs())
-            .unwrap_or_default();
+    fn update_time_series(&mut self, timestamp: u64) {
         let sent = self.dest_stats.values().map(|(s, _)| s).sum();
         let acked = self.dest_stats.values().map(|(_, a)| a).sum();
-        self.time_series.push(HostMetricsTimePoint {
+        self.time_series.record(
             timestamp,
-            sent,
-            acked,
-        });
+            HostMetricsTimePoint {
+                timestamp,
+                sent,
+                acked,
+            },
+        );
     }
 
     /// Record a shortcut used by the host.
@@ -125,9 +299,13 @@ impl crate::simulation::metrics::HostMetrics {
         self.shortcuts += 1;
     }
 
-    /// Record the latency for a message sent by the host.
-    pub fn record_latency(&mut self, latency: Duration) {
+    /// Record the latency for a message sent by the host to `destination`.
+    pub fn record_latency(&mut self, destination: NodeId, latency: Duration, now_ms: u64) {
         self.latencies.push(latency);
+        self.route_latencies
+            .entry(destination)
+            .or_default()
+            .record(now_ms, latency);
     }
 
     pub fn number_of_packets_sent(&self) -> u64 {
@@ -158,7 +336,7 @@ impl crate::simulation::metrics::DroneMetrics {
             .unwrap_or(&0)
     }
 
-    pub fn update_pdr(&mut self, successful: bool) {
+    pub fn update_pdr(&mut self, successful: bool, timestamp: u64) {
         // Add success/failure to rolling window
         self.rolling_window.push(successful);
         if self.rolling_window.len() > crate::simulation::metrics::ROLLING_WINDOW_SIZE {
@@ -175,23 +353,21 @@ impl crate::simulation::metrics::DroneMetrics {
 
         let sent = self.number_of_msg_fragments_sent();
         let dropped = self.drops;
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map(|d| d.as_secs())
-            .unwrap_or_default();
 
-        // Maintain rolling window for time series
-        self.time_series.push(MetricsTimePoint {
+        self.time_series.record(
             timestamp,
-            sent,
-            dropped,
-        });
+            MetricsTimePoint {
+                timestamp,
+                sent,
+                dropped,
+            },
+        );
     }
 
-    pub fn record_packet(&mut self, packet_type: PacketTypeLabel) {
+    pub fn record_packet(&mut self, packet_type: PacketTypeLabel, now_ms: u64) {
         *self.packet_type_counts.entry(packet_type).or_insert(0) += 1;
         if packet_type == PacketTypeLabel::MsgFragment {
-            self.update_pdr(true);
+            self.update_pdr(true, now_ms);
         }
     }
 
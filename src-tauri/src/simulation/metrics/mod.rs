@@ -1,11 +1,18 @@
+pub mod edges;
+pub mod latency;
 pub mod metrics_handler;
+pub mod time_series;
 
+use crate::simulation::listener::shortcuts::ShortcutFailure;
+use crate::simulation::metrics::edges::EdgeMetrics;
+use crate::simulation::metrics::latency::LatencyRecorder;
+use crate::simulation::metrics::time_series::{TimePoint, TimeSeries};
 use common_utils::PacketTypeHeader;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::Duration;
 use wg_2024::network::NodeId;
-use wg_2024::packet::PacketType;
+use wg_2024::packet::{NackType, PacketType};
 
 const ROLLING_WINDOW_SIZE: usize = 1000;
 
@@ -14,12 +21,28 @@ pub struct Metrics {
     /// Global heatmap: (source, dest) -> packet sent.
     pub global_heatmap: HashMap<(NodeId, NodeId), u64>,
 
+    /// Per directed edge metrics: (source, dest) -> metrics.
+    pub edge_metrics: HashMap<(NodeId, NodeId), EdgeMetrics>,
+
+    /// NACKs referencing a node: error node -> (nack type -> count).
+    pub nack_error_nodes: HashMap<NodeId, HashMap<NackTypeLabel, u64>>,
+
     pub drone_metrics: HashMap<NodeId, DroneMetrics>,
 
     pub host_metrics: HashMap<NodeId, HostMetrics>,
+
+    /// Deliveries of the packets sent through the simulation controller, by destination.
+    pub shortcut_deliveries: HashMap<NodeId, ShortcutDeliveryMetrics>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ShortcutDeliveryMetrics {
+    pub delivered: u64,
+    /// Packets not delivered, by reason
+    pub failures: HashMap<ShortcutFailure, u64>,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default)]
 pub struct DroneMetrics {
     /// Number of packets dropped by the drone
     pub drops: u64,
@@ -34,7 +57,11 @@ pub struct DroneMetrics {
     /// Count for each packet type sent by the drone
     pub packet_type_counts: HashMap<PacketTypeLabel, u64>,
 
-    pub time_series: Vec<MetricsTimePoint>,
+    /// Count for each type of NACK originated by the drone
+    pub nack_type_counts: HashMap<NackTypeLabel, u64>,
+
+    /// Time series for the number of fragments sent and dropped
+    pub time_series: TimeSeries<MetricsTimePoint>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Eq, Hash)]
@@ -62,6 +89,25 @@ impl From<&PacketType> for crate::simulation::metrics::PacketTypeLabel {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Eq, Hash)]
+pub enum NackTypeLabel {
+    ErrorInRouting,
+    DestinationIsDrone,
+    Dropped,
+    UnexpectedRecipient,
+}
+
+impl From<&NackType> for NackTypeLabel {
+    fn from(nack_type: &NackType) -> Self {
+        match nack_type {
+            NackType::ErrorInRouting(_) => NackTypeLabel::ErrorInRouting,
+            NackType::DestinationIsDrone => NackTypeLabel::DestinationIsDrone,
+            NackType::Dropped => NackTypeLabel::Dropped,
+            NackType::UnexpectedRecipient(_) => NackTypeLabel::UnexpectedRecipient,
+        }
+    }
+}
+
 impl From<&PacketTypeHeader> for crate::simulation::metrics::PacketTypeLabel {
     fn from(pt: &PacketTypeHeader) -> Self {
         match pt {
@@ -81,11 +127,18 @@ impl From<&PacketTypeHeader> for crate::simulation::metrics::PacketTypeLabel {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetricsTimePoint {
+    /// Run-relative timestamp in milliseconds
     pub timestamp: u64,
     pub sent: u64,
     pub dropped: u64,
 }
 
+impl TimePoint for MetricsTimePoint {
+    fn set_timestamp(&mut self, timestamp: u64) {
+        self.timestamp = timestamp;
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct HostMetrics {
     /// For each destination: (sent, acked)
@@ -96,13 +149,22 @@ pub struct HostMetrics {
     pub packet_type_counts: HashMap<crate::simulation::metrics::PacketTypeLabel, u64>,
     /// Latency for each message sent by the host. It could be used to compute number of Message sent
     pub latencies: Vec<Duration>,
-    /// Time series for the number of packets sent and dropped
-    pub time_series: Vec<HostMetricsTimePoint>,
+    /// Latency histogram of the messages sent to each destination
+    pub route_latencies: HashMap<NodeId, LatencyRecorder>,
+    /// Time series for the number of packets sent and acked
+    pub time_series: TimeSeries<HostMetricsTimePoint>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HostMetricsTimePoint {
+    /// Run-relative timestamp in milliseconds
     pub timestamp: u64,
     pub sent: u64,
     pub acked: u64,
 }
+
+impl TimePoint for HostMetricsTimePoint {
+    fn set_timestamp(&mut self, timestamp: u64) {
+        self.timestamp = timestamp;
+    }
+}
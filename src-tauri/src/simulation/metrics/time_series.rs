@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Resolution of the buckets returned by a time series query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Resolution {
+    /// 100ms buckets, kept for the last minute
+    Raw,
+    /// 1s buckets, kept for the last 15 minutes
+    #[default]
+    Second,
+    /// 10s buckets, kept for the last 2 hours
+    TenSeconds,
+}
+
+impl Resolution {
+    fn bucket_ms(self) -> u64 {
+        match self {
+            Resolution::Raw => 100,
+            Resolution::Second => 1_000,
+            Resolution::TenSeconds => 10_000,
+        }
+    }
+
+    fn retention(self) -> usize {
+        match self {
+            Resolution::Raw => 600,
+            Resolution::Second => 900,
+            Resolution::TenSeconds => 720,
+        }
+    }
+}
+
+/// A point of a time series made of cumulative counters.
+pub trait TimePoint: Clone {
+    fn set_timestamp(&mut self, timestamp: u64);
+}
+
+/// The aggregation of the points recorded during a bucket: since the counters are
+/// cumulative, the last point summarizes the whole bucket.
+#[derive(Debug, Clone)]
+struct SeriesBucket<T> {
+    /// Start of the bucket, in milliseconds
+    start: u64,
+    /// Number of points recorded in the bucket
+    samples: u64,
+    last: T,
+}
+
+/// Fixed-interval buckets of a single resolution, oldest first.
+#[derive(Debug, Clone)]
+struct Tier<T> {
+    resolution: Resolution,
+    buckets: VecDeque<SeriesBucket<T>>,
+}
+
+impl<T: TimePoint> Tier<T> {
+    fn new(resolution: Resolution) -> Self {
+        Self {
+            resolution,
+            buckets: VecDeque::new(),
+        }
+    }
+
+    fn record(&mut self, now_ms: u64, point: &T) {
+        let start = now_ms - now_ms % self.resolution.bucket_ms();
+        if let Some(bucket) = self.buckets.back_mut() {
+            // Points recorded out of order are folded in the latest bucket
+            if bucket.start >= start {
+                bucket.samples += 1;
+                bucket.last = point.clone();
+                return;
+            }
+        }
+
+        self.buckets.push_back(SeriesBucket {
+            start,
+            samples: 1,
+            last: point.clone(),
+        });
+        if self.buckets.len() > self.resolution.retention() {
+            self.buckets.pop_front();
+        }
+    }
+}
+
+/// A bounded time series with multiple retention tiers.
+#[derive(Debug, Clone)]
+pub struct TimeSeries<T> {
+    raw: Tier<T>,
+    seconds: Tier<T>,
+    ten_seconds: Tier<T>,
+}
+
+impl<T: TimePoint> Default for TimeSeries<T> {
+    fn default() -> Self {
+        Self {
+            raw: Tier::new(Resolution::Raw),
+            seconds: Tier::new(Resolution::Second),
+            ten_seconds: Tier::new(Resolution::TenSeconds),
+        }
+    }
+}
+
+impl<T: TimePoint> TimeSeries<T> {
+    pub fn record(&mut self, now_ms: u64, point: T) {
+        self.raw.record(now_ms, &point);
+        self.seconds.record(now_ms, &point);
+        self.ten_seconds.record(now_ms, &point);
+    }
+
+    /// Returns one point per bucket in `[from_ms, to_ms]`, timestamped with the bucket start.
+    pub fn query(
+        &self,
+        from_ms: Option<u64>,
+        to_ms: Option<u64>,
+        resolution: Resolution,
+    ) -> Vec<SeriesPoint<T>> {
+        self.tier(resolution)
+            .buckets
+            .iter()
+            .filter(|b| from_ms.is_none_or(|from| b.start >= from))
+            .filter(|b| to_ms.is_none_or(|to| b.start <= to))
+            .map(|b| {
+                let mut point = b.last.clone();
+                point.set_timestamp(b.start);
+                SeriesPoint {
+                    point,
+                    samples: b.samples,
+                }
+            })
+            .collect()
+    }
+
+    fn tier(&self, resolution: Resolution) -> &Tier<T> {
+        match resolution {
+            Resolution::Raw => &self.raw,
+            Resolution::Second => &self.seconds,
+            Resolution::TenSeconds => &self.ten_seconds,
+        }
+    }
+}
+
+/// A point returned by a time series query.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeriesPoint<T> {
+    #[serde(flatten)]
+    pub point: T,
+    /// Number of points aggregated in the bucket
+    pub samples: u64,
+}
@@ -1,7 +1,12 @@
+pub mod clock;
 pub mod configs;
+pub mod events;
 mod initializer;
+pub mod journal;
 pub mod listener;
 pub mod state;
 pub mod topology;
 pub mod controller;
 pub mod metrics;
+pub mod runs;
+pub mod sniffer;
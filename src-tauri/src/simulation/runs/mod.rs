@@ -0,0 +1,113 @@
+pub mod runs_handler;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use wg_2024::network::NodeId;
+
+/// Maximum number of runs kept in the archive. The oldest run is evicted first.
+const MAX_ARCHIVED_RUNS: usize = 20;
+
+/// Two-sided p-value under which a difference is considered significant.
+const SIGNIFICANCE_LEVEL: f64 = 0.05;
+
+/// Snapshot of the metrics of a finished simulation run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub run_id: u64,
+    /// UNIX timestamp (seconds) of the start of the run
+    pub started_at: u64,
+    /// UNIX timestamp (seconds) of the end of the run
+    pub ended_at: u64,
+    /// Duration of the run in milliseconds, measured by the simulation clock
+    pub duration_ms: u64,
+    /// Fragments sent by the hosts
+    pub fragments_sent: u64,
+    /// Acks received by the hosts
+    pub fragments_acked: u64,
+    /// Latency of each message sent by the hosts, in milliseconds
+    pub latencies: Vec<u64>,
+    /// Per drone statistics
+    pub drones: HashMap<NodeId, DroneRunStats>,
+    /// Shortcuts used by drones and hosts
+    pub shortcuts: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DroneRunStats {
+    pub group: Option<String>,
+    pub fragments_sent: u64,
+    pub drops: u64,
+}
+
+/// Short description of an archived run, used to list the archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunInfo {
+    pub run_id: u64,
+    pub started_at: u64,
+    pub ended_at: u64,
+    pub drones: usize,
+    pub delivery_ratio: f64,
+}
+
+/// Result of the comparison between a baseline and a candidate run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunComparison {
+    pub baseline_run: u64,
+    pub candidate_run: u64,
+    pub delivery_ratio: MetricDelta,
+    pub delivery_significance: Significance,
+    pub latency: LatencyComparison,
+    pub drones: Vec<DropsDelta<NodeId>>,
+    pub groups: Vec<DropsDelta<String>>,
+    pub shortcuts: MetricDelta,
+}
+
+/// A metric measured in both runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricDelta {
+    pub baseline: f64,
+    pub candidate: f64,
+    pub delta: f64,
+    /// Delta relative to the baseline, `None` if the baseline is zero
+    pub relative_delta: Option<f64>,
+}
+
+/// Outcome of a two-sided statistical test.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Significance {
+    pub z_score: f64,
+    pub p_value: f64,
+    pub significant: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyComparison {
+    pub baseline: LatencyDistribution,
+    pub candidate: LatencyDistribution,
+    pub mean: MetricDelta,
+    pub p50: MetricDelta,
+    pub p90: MetricDelta,
+    pub p99: MetricDelta,
+    pub significance: Significance,
+}
+
+/// Latency distribution of a run, in milliseconds.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencyDistribution {
+    pub count: u64,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+/// Drops of a drone (or of an implementation group) in both runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DropsDelta<K> {
+    pub key: K,
+    pub drops: MetricDelta,
+    pub drop_rate: MetricDelta,
+    pub significance: Significance,
+}
@@ -0,0 +1,413 @@
+use crate::simulation::runs::{
+    DroneRunStats, DropsDelta, LatencyComparison, LatencyDistribution, MetricDelta, RunComparison,
+    RunInfo, RunSummary, Significance, MAX_ARCHIVED_RUNS, SIGNIFICANCE_LEVEL,
+};
+use crate::simulation::state::SimulationState;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::Hash;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+impl RunSummary {
+    /// Builds the summary of the current run from the metrics collected so far.
+    pub fn from_state(state: &SimulationState) -> Self {
+        let metrics = state.get_metrics();
+
+        let drones = metrics
+            .drone_metrics
+            .iter()
+            .map(|(&node_id, drone_metrics)| {
+                (
+                    node_id,
+                    DroneRunStats {
+                        group: state.get_graph().get_drone_group(node_id).cloned(),
+                        fragments_sent: drone_metrics.number_of_msg_fragments_sent(),
+                        drops: drone_metrics.drops,
+                    },
+                )
+            })
+            .collect();
+
+        let latencies = metrics
+            .host_metrics
+            .values()
+            .flat_map(|m| m.latencies.iter().map(|lat| lat.as_millis() as u64))
+            .collect();
+
+        let shortcuts = metrics
+            .drone_metrics
+            .values()
+            .map(|m| m.shortcuts)
+            .sum::<u64>()
+            + metrics
+                .host_metrics
+                .values()
+                .map(|m| m.shortcuts)
+                .sum::<u64>();
+
+        RunSummary {
+            run_id: state.get_run_id(),
+            started_at: state.get_clock().started_at() / 1000,
+            ended_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            duration_ms: state.get_clock().now_ms(),
+            fragments_sent: metrics
+                .host_metrics
+                .values()
+                .map(|m| m.number_of_fragments_sent())
+                .sum(),
+            fragments_acked: metrics
+                .host_metrics
+                .values()
+                .flat_map(|m| m.dest_stats.values().map(|(_, acked)| acked))
+                .sum(),
+            latencies,
+            drones,
+            shortcuts,
+        }
+    }
+
+    pub fn delivery_ratio(&self) -> f64 {
+        ratio(self.fragments_acked, self.fragments_sent)
+    }
+
+    pub fn info(&self) -> RunInfo {
+        RunInfo {
+            run_id: self.run_id,
+            started_at: self.started_at,
+            ended_at: self.ended_at,
+            drones: self.drones.len(),
+            delivery_ratio: self.delivery_ratio(),
+        }
+    }
+
+    /// Aggregates the drone statistics by implementation group.
+    fn group_stats(&self) -> HashMap<String, (u64, u64)> {
+        let mut groups: HashMap<String, (u64, u64)> = HashMap::new();
+        for drone in self.drones.values() {
+            let group = drone.group.clone().unwrap_or_else(|| "Unknown".to_string());
+            let entry = groups.entry(group).or_insert((0, 0));
+            entry.0 += drone.fragments_sent;
+            entry.1 += drone.drops;
+        }
+        groups
+    }
+}
+
+impl SimulationState {
+    /// Stores the summary of the current run in the archive.
+    pub fn archive_current_run(&mut self) {
+        let summary = RunSummary::from_state(self);
+        let archived_runs = self.get_archived_runs_mut();
+        archived_runs.push(summary);
+        if archived_runs.len() > MAX_ARCHIVED_RUNS {
+            archived_runs.remove(0);
+        }
+    }
+}
+
+/// Compares two archived runs, using `baseline` as the reference.
+pub fn compare_runs(baseline: &RunSummary, candidate: &RunSummary) -> RunComparison {
+    let drone_stats = |run: &RunSummary| -> HashMap<_, _> {
+        run.drones
+            .iter()
+            .map(|(&id, d)| (id, (d.fragments_sent, d.drops)))
+            .collect()
+    };
+
+    RunComparison {
+        baseline_run: baseline.run_id,
+        candidate_run: candidate.run_id,
+        delivery_ratio: metric_delta(baseline.delivery_ratio(), candidate.delivery_ratio()),
+        delivery_significance: two_proportion_test(
+            (baseline.fragments_acked, baseline.fragments_sent),
+            (candidate.fragments_acked, candidate.fragments_sent),
+        ),
+        latency: compare_latencies(&baseline.latencies, &candidate.latencies),
+        drones: compare_drops(&drone_stats(baseline), &drone_stats(candidate)),
+        groups: compare_drops(&baseline.group_stats(), &candidate.group_stats()),
+        shortcuts: metric_delta(baseline.shortcuts as f64, candidate.shortcuts as f64),
+    }
+}
+
+/// Compares the drops of each key, given as `key -> (fragments sent, drops)`.
+fn compare_drops<K: Clone + Ord + Hash>(
+    baseline: &HashMap<K, (u64, u64)>,
+    candidate: &HashMap<K, (u64, u64)>,
+) -> Vec<DropsDelta<K>> {
+    let keys: BTreeSet<&K> = baseline.keys().chain(candidate.keys()).collect();
+
+    keys.into_iter()
+        .map(|key| {
+            let (b_sent, b_drops) = baseline.get(key).copied().unwrap_or_default();
+            let (c_sent, c_drops) = candidate.get(key).copied().unwrap_or_default();
+            DropsDelta {
+                key: key.clone(),
+                drops: metric_delta(b_drops as f64, c_drops as f64),
+                drop_rate: metric_delta(
+                    ratio(b_drops, b_sent + b_drops),
+                    ratio(c_drops, c_sent + c_drops),
+                ),
+                significance: two_proportion_test(
+                    (b_drops, b_sent + b_drops),
+                    (c_drops, c_sent + c_drops),
+                ),
+            }
+        })
+        .collect()
+}
+
+fn compare_latencies(baseline: &[u64], candidate: &[u64]) -> LatencyComparison {
+    let baseline = LatencyDistribution::from_samples(baseline);
+    let candidate = LatencyDistribution::from_samples(candidate);
+
+    // Welch's test with the normal approximation
+    let standard_error = (baseline.std_dev.powi(2) / baseline.count.max(1) as f64
+        + candidate.std_dev.powi(2) / candidate.count.max(1) as f64)
+        .sqrt();
+    let significance = if baseline.count < 2 || candidate.count < 2 {
+        Significance::none()
+    } else {
+        Significance::from_z_score(z_score(candidate.mean - baseline.mean, standard_error))
+    };
+
+    LatencyComparison {
+        mean: metric_delta(baseline.mean, candidate.mean),
+        p50: metric_delta(baseline.p50, candidate.p50),
+        p90: metric_delta(baseline.p90, candidate.p90),
+        p99: metric_delta(baseline.p99, candidate.p99),
+        baseline,
+        candidate,
+        significance,
+    }
+}
+
+impl LatencyDistribution {
+    fn from_samples(samples: &[u64]) -> Self {
+        if samples.is_empty() {
+            return LatencyDistribution::default();
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+
+        let count = sorted.len() as f64;
+        let mean = sorted.iter().sum::<u64>() as f64 / count;
+        let variance = if sorted.len() > 1 {
+            sorted
+                .iter()
+                .map(|&s| (s as f64 - mean).powi(2))
+                .sum::<f64>()
+                / (count - 1.0)
+        } else {
+            0.0
+        };
+
+        LatencyDistribution {
+            count: sorted.len() as u64,
+            mean,
+            std_dev: variance.sqrt(),
+            p50: percentile(&sorted, 0.50),
+            p90: percentile(&sorted, 0.90),
+            p99: percentile(&sorted, 0.99),
+            max: *sorted.last().unwrap_or(&0) as f64,
+        }
+    }
+}
+
+impl Significance {
+    fn none() -> Self {
+        Significance {
+            z_score: 0.0,
+            p_value: 1.0,
+            significant: false,
+        }
+    }
+
+    fn from_z_score(z_score: f64) -> Self {
+        let p_value = 2.0 * (1.0 - normal_cdf(z_score.abs()));
+        Significance {
+            z_score,
+            p_value,
+            significant: p_value < SIGNIFICANCE_LEVEL,
+        }
+    }
+}
+
+/// Two-proportion z-test between `(successes, trials)` of the two runs.
+fn two_proportion_test(baseline: (u64, u64), candidate: (u64, u64)) -> Significance {
+    let (x1, n1) = (baseline.0 as f64, baseline.1 as f64);
+    let (x2, n2) = (candidate.0 as f64, candidate.1 as f64);
+    if n1 == 0.0 || n2 == 0.0 {
+        return Significance::none();
+    }
+
+    let pooled = (x1 + x2) / (n1 + n2);
+    let standard_error = (pooled * (1.0 - pooled) * (1.0 / n1 + 1.0 / n2)).sqrt();
+    Significance::from_z_score(z_score(x2 / n2 - x1 / n1, standard_error))
+}
+
+fn z_score(difference: f64, standard_error: f64) -> f64 {
+    if standard_error > 0.0 {
+        difference / standard_error
+    } else {
+        0.0
+    }
+}
+
+fn metric_delta(baseline: f64, candidate: f64) -> MetricDelta {
+    MetricDelta {
+        baseline,
+        candidate,
+        delta: candidate - baseline,
+        relative_delta: (baseline != 0.0).then(|| (candidate - baseline) / baseline),
+    }
+}
+
+fn ratio(numerator: u64, denominator: u64) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        (numerator as f64 / denominator as f64).min(1.0)
+    }
+}
+
+/// Nearest-rank percentile of an already sorted slice.
+fn percentile(sorted: &[u64], quantile: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (quantile * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1] as f64
+}
+
+/// Standard normal CDF, using the Abramowitz-Stegun approximation of `erf`.
+fn normal_cdf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs() / std::f64::consts::SQRT_2);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-(x * x) / 2.0).exp();
+    if x >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{} is not within {} of {}",
+            actual,
+            tolerance,
+            expected
+        );
+    }
+
+    #[test]
+    fn normal_cdf_matches_the_standard_normal_table() {
+        assert_close(normal_cdf(0.0), 0.5, 1e-7);
+        assert_close(normal_cdf(1.0), 0.841_344_7, 1e-6);
+        assert_close(normal_cdf(-1.0), 0.158_655_3, 1e-6);
+        assert_close(normal_cdf(1.959_964), 0.975, 1e-6);
+        assert_close(normal_cdf(-3.0), 0.001_349_9, 1e-6);
+    }
+
+    #[test]
+    fn p_value_is_two_sided() {
+        let significance = Significance::from_z_score(1.959_964);
+        assert_close(significance.p_value, 0.05, 1e-5);
+
+        let significance = Significance::from_z_score(-3.0);
+        assert_close(significance.p_value, 0.002_699_8, 1e-6);
+        assert!(significance.significant);
+    }
+
+    #[test]
+    fn two_proportion_test_uses_the_pooled_proportion() {
+        // pooled = 115 / 200, se = sqrt(0.575 * 0.425 * (1/100 + 1/100)) = 0.069911
+        let significance = two_proportion_test((50, 100), (65, 100));
+        assert_close(significance.z_score, 2.145_6, 1e-4);
+        assert_close(significance.p_value, 0.031_9, 1e-3);
+        assert!(significance.significant);
+
+        let significance = two_proportion_test((50, 100), (52, 100));
+        assert!(!significance.significant);
+    }
+
+    #[test]
+    fn two_proportion_test_without_trials_is_not_significant() {
+        let significance = two_proportion_test((0, 0), (10, 20));
+        assert_eq!(significance.z_score, 0.0);
+        assert_eq!(significance.p_value, 1.0);
+        assert!(!significance.significant);
+    }
+
+    #[test]
+    fn percentile_uses_the_nearest_rank() {
+        let sorted: Vec<u64> = (1..=10).collect();
+        assert_eq!(percentile(&sorted, 0.5), 5.0);
+        assert_eq!(percentile(&sorted, 0.9), 9.0);
+        assert_eq!(percentile(&sorted, 0.99), 10.0);
+        assert_eq!(percentile(&sorted, 0.0), 1.0);
+        assert_eq!(percentile(&[], 0.5), 0.0);
+    }
+
+    #[test]
+    fn latency_distribution_uses_the_sample_variance() {
+        let distribution = LatencyDistribution::from_samples(&[9, 2, 4, 4, 4, 5, 5, 7]);
+        assert_eq!(distribution.count, 8);
+        assert_close(distribution.mean, 5.0, 1e-12);
+        assert_close(distribution.std_dev, (32.0f64 / 7.0).sqrt(), 1e-12);
+        assert_eq!(distribution.p50, 4.0);
+        assert_eq!(distribution.p90, 9.0);
+        assert_eq!(distribution.max, 9.0);
+    }
+
+    #[test]
+    fn welch_test_compares_the_mean_latencies() {
+        // Both variances are 10: se = sqrt(10 / 5 + 10 / 5) = 2
+        let comparison = compare_latencies(&[10, 12, 14, 16, 18], &[20, 22, 24, 26, 28]);
+        assert_close(comparison.mean.delta, 10.0, 1e-12);
+        assert_close(comparison.significance.z_score, 5.0, 1e-12);
+        assert!(comparison.significance.significant);
+
+        let comparison = compare_latencies(&[10, 12, 14], &[10, 12, 14]);
+        assert_eq!(comparison.significance.z_score, 0.0);
+        assert!(!comparison.significance.significant);
+    }
+
+    #[test]
+    fn welch_test_needs_two_samples_per_run() {
+        let comparison = compare_latencies(&[10], &[20, 22, 24]);
+        assert_eq!(comparison.significance.p_value, 1.0);
+        assert!(!comparison.significance.significant);
+    }
+
+    #[test]
+    fn compare_drops_fills_the_missing_keys_with_zeros() {
+        let baseline = HashMap::from([(1, (90, 10)), (2, (100, 0))]);
+        let candidate = HashMap::from([(1, (80, 20))]);
+
+        let deltas = compare_drops(&baseline, &candidate);
+        assert_eq!(deltas.len(), 2);
+        assert_eq!(deltas[0].key, 1);
+        assert_close(deltas[0].drop_rate.baseline, 0.1, 1e-12);
+        assert_close(deltas[0].drop_rate.candidate, 0.2, 1e-12);
+        assert_eq!(deltas[1].key, 2);
+        assert_eq!(deltas[1].drops.candidate, 0.0);
+        assert_eq!(deltas[1].drop_rate.candidate, 0.0);
+    }
+
+    #[test]
+    fn relative_delta_is_undefined_for_a_zero_baseline() {
+        assert_eq!(metric_delta(0.0, 5.0).relative_delta, None);
+        assert_eq!(metric_delta(4.0, 5.0).relative_delta, Some(0.25));
+    }
+}
@@ -0,0 +1,40 @@
+pub mod pcapng;
+pub mod sniffer_handler;
+
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Instant;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Maximum number of captured packets kept. The oldest packet is evicted first.
+const MAX_CAPTURED_PACKETS: usize = 100_000;
+
+/// A packet delivered on the inbound channel of a node, as seen by the sniffer.
+#[derive(Debug, Clone)]
+pub struct CapturedPacket {
+    /// The run during which the packet was delivered
+    pub run_id: u64,
+    /// The node the packet was delivered to
+    pub node: NodeId,
+    /// UNIX timestamp in milliseconds at which the packet was delivered
+    pub timestamp: u64,
+    /// Milliseconds elapsed since the start of the run
+    pub sim_time_ms: u64,
+    pub packet: Packet,
+}
+
+/// Passive tap on the inbound channels of the nodes.
+///
+/// Every packet sent to a tapped node goes through a forwarder thread that copies it in
+/// `pending` before handing it to the node, so the controller sees the traffic actually
+/// exchanged whatever the nodes report. The listener then moves the pending packets to
+/// `captured` and to the event log.
+#[derive(Debug, Default)]
+pub struct Sniffer {
+    /// Packets seen by the forwarders and not yet collected by the listener
+    pending: Arc<Mutex<Vec<(NodeId, Instant, Packet)>>>,
+    /// Packets collected by the listener, oldest first. Kept across runs.
+    captured: VecDeque<CapturedPacket>,
+}
@@ -0,0 +1,359 @@
+use crate::simulation::sniffer::CapturedPacket;
+use std::collections::HashMap;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NackType, NodeType, Packet, PacketType};
+
+/// Link type of the captured packets: `LINKTYPE_USER0`, reserved for private use.
+pub const LINK_TYPE: u16 = 147;
+
+/// Version of the binary encoding of the packets, first byte of every captured frame.
+pub const ENCODING_VERSION: u8 = 1;
+
+const SECTION_HEADER_BLOCK: u32 = 0x0A0D_0D0A;
+const INTERFACE_DESCRIPTION_BLOCK: u32 = 0x0000_0001;
+const ENHANCED_PACKET_BLOCK: u32 = 0x0000_0006;
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+
+const OPT_END_OF_OPT: u16 = 0;
+const OPT_COMMENT: u16 = 1;
+const OPT_IF_NAME: u16 = 2;
+const OPT_IF_TSRESOL: u16 = 9;
+
+/// Timestamps are written in milliseconds, as captured.
+const TIMESTAMP_RESOLUTION: u8 = 3;
+
+/// Writes `packets` as a pcapng capture, with one interface per receiving node.
+///
+/// Interfaces are numbered by increasing node ID and named `node-<id>`. Every packet is
+/// encoded with `encode_packet` and annotated with its run-relative time.
+pub fn write_capture<'a>(packets: impl IntoIterator<Item = &'a CapturedPacket>) -> Vec<u8> {
+    let packets: Vec<&CapturedPacket> = packets.into_iter().collect();
+
+    let mut nodes: Vec<NodeId> = packets.iter().map(|p| p.node).collect();
+    nodes.sort_unstable();
+    nodes.dedup();
+    let interfaces: HashMap<NodeId, u32> = nodes
+        .iter()
+        .enumerate()
+        .map(|(index, &node)| (node, index as u32))
+        .collect();
+
+    let mut out = Vec::new();
+    write_section_header(&mut out);
+    for &node in &nodes {
+        write_interface_description(&mut out, node);
+    }
+    for packet in packets {
+        write_enhanced_packet(&mut out, interfaces[&packet.node], packet);
+    }
+    out
+}
+
+/// Encodes `packet` in the stable binary format read by the Wireshark dissector.
+///
+/// Every integer is big-endian. The frame is made of:
+///
+/// * `u8` encoding version, `u8` packet type (0 = MsgFragment, 1 = Ack, 2 = Nack,
+///   3 = FloodRequest, 4 = FloodResponse), `u64` session ID
+/// * `u8` hop index, `u8` number of hops, one `u8` per hop
+/// * the payload:
+///   * MsgFragment: `u64` fragment index, `u64` total fragments, `u8` length, 128 bytes of data
+///   * Ack: `u64` fragment index
+///   * Nack: `u64` fragment index, `u8` NACK type (0 = ErrorInRouting, 1 = DestinationIsDrone,
+///     2 = Dropped, 3 = UnexpectedRecipient), `u8` node referenced by the NACK or 0
+///   * FloodRequest: `u64` flood ID, `u8` initiator, `u8` path length, then the path
+///   * FloodResponse: `u64` flood ID, `u8` path length, then the path
+///
+/// A path is a list of `u8` node ID and `u8` node type (0 = Client, 1 = Drone, 2 = Server).
+pub fn encode_packet(packet: &Packet) -> Vec<u8> {
+    let mut out = vec![ENCODING_VERSION];
+    let packet_type: u8 = match packet.pack_type {
+        PacketType::MsgFragment(_) => 0,
+        PacketType::Ack(_) => 1,
+        PacketType::Nack(_) => 2,
+        PacketType::FloodRequest(_) => 3,
+        PacketType::FloodResponse(_) => 4,
+    };
+    out.push(packet_type);
+    out.extend_from_slice(&packet.session_id.to_be_bytes());
+
+    let header = &packet.routing_header;
+    out.push(header.hop_index as u8);
+    out.push(header.hops.len() as u8);
+    out.extend_from_slice(&header.hops);
+
+    match &packet.pack_type {
+        PacketType::MsgFragment(fragment) => {
+            out.extend_from_slice(&fragment.fragment_index.to_be_bytes());
+            out.extend_from_slice(&fragment.total_n_fragments.to_be_bytes());
+            out.push(fragment.length);
+            out.extend_from_slice(&fragment.data);
+        }
+        PacketType::Ack(ack) => out.extend_from_slice(&ack.fragment_index.to_be_bytes()),
+        PacketType::Nack(nack) => {
+            out.extend_from_slice(&nack.fragment_index.to_be_bytes());
+            let (nack_type, node) = match &nack.nack_type {
+                NackType::ErrorInRouting(node) => (0, *node),
+                NackType::DestinationIsDrone => (1, 0),
+                NackType::Dropped => (2, 0),
+                NackType::UnexpectedRecipient(node) => (3, *node),
+            };
+            out.push(nack_type);
+            out.push(node);
+        }
+        PacketType::FloodRequest(request) => {
+            out.extend_from_slice(&request.flood_id.to_be_bytes());
+            out.push(request.initiator_id);
+            encode_path(&mut out, &request.path_trace);
+        }
+        PacketType::FloodResponse(response) => {
+            out.extend_from_slice(&response.flood_id.to_be_bytes());
+            encode_path(&mut out, &response.path_trace);
+        }
+    }
+    out
+}
+
+fn encode_path(out: &mut Vec<u8>, path: &[(NodeId, NodeType)]) {
+    out.push(path.len() as u8);
+    for (node, node_type) in path {
+        out.push(*node);
+        out.push(match node_type {
+            NodeType::Client => 0,
+            NodeType::Drone => 1,
+            NodeType::Server => 2,
+        });
+    }
+}
+
+fn write_section_header(out: &mut Vec<u8>) {
+    let mut body = Vec::new();
+    body.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+    body.extend_from_slice(&1u16.to_le_bytes());
+    body.extend_from_slice(&0u16.to_le_bytes());
+    // Section length not specified
+    body.extend_from_slice(&(-1i64).to_le_bytes());
+    write_option(&mut body, OPT_END_OF_OPT, &[]);
+    write_block(out, SECTION_HEADER_BLOCK, &body);
+}
+
+fn write_interface_description(out: &mut Vec<u8>, node: NodeId) {
+    let mut body = Vec::new();
+    body.extend_from_slice(&LINK_TYPE.to_le_bytes());
+    body.extend_from_slice(&0u16.to_le_bytes());
+    // No snapshot length limit
+    body.extend_from_slice(&0u32.to_le_bytes());
+    write_option(&mut body, OPT_IF_NAME, format!("node-{}", node).as_bytes());
+    write_option(&mut body, OPT_IF_TSRESOL, &[TIMESTAMP_RESOLUTION]);
+    write_option(&mut body, OPT_END_OF_OPT, &[]);
+    write_block(out, INTERFACE_DESCRIPTION_BLOCK, &body);
+}
+
+fn write_enhanced_packet(out: &mut Vec<u8>, interface: u32, packet: &CapturedPacket) {
+    let data = encode_packet(&packet.packet);
+    let mut body = Vec::new();
+    body.extend_from_slice(&interface.to_le_bytes());
+    body.extend_from_slice(&((packet.timestamp >> 32) as u32).to_le_bytes());
+    body.extend_from_slice(&(packet.timestamp as u32).to_le_bytes());
+    body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    body.extend_from_slice(&data);
+    pad(&mut body);
+    let comment = format!("run {} +{}ms", packet.run_id, packet.sim_time_ms);
+    write_option(&mut body, OPT_COMMENT, comment.as_bytes());
+    write_option(&mut body, OPT_END_OF_OPT, &[]);
+    write_block(out, ENHANCED_PACKET_BLOCK, &body);
+}
+
+fn write_option(body: &mut Vec<u8>, code: u16, value: &[u8]) {
+    body.extend_from_slice(&code.to_le_bytes());
+    body.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    body.extend_from_slice(value);
+    pad(body);
+}
+
+/// Writes a block: its type, its total length, the body and the total length again.
+fn write_block(out: &mut Vec<u8>, block_type: u32, body: &[u8]) {
+    let total_length = (body.len() + 12) as u32;
+    out.extend_from_slice(&block_type.to_le_bytes());
+    out.extend_from_slice(&total_length.to_le_bytes());
+    out.extend_from_slice(body);
+    out.extend_from_slice(&total_length.to_le_bytes());
+}
+
+/// Pads to a multiple of 4 bytes, as required by every pcapng field.
+fn pad(buf: &mut Vec<u8>) {
+    buf.resize(buf.len().next_multiple_of(4), 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Ack, FloodRequest, Fragment, Nack, FRAGMENT_DSIZE};
+
+    fn packet(hops: Vec<NodeId>, pack_type: PacketType) -> Packet {
+        Packet {
+            routing_header: SourceRoutingHeader { hop_index: 1, hops },
+            session_id: 0x0102_0304_0506_0708,
+            pack_type,
+        }
+    }
+
+    fn captured(node: NodeId, timestamp: u64, packet: Packet) -> CapturedPacket {
+        CapturedPacket {
+            run_id: 2,
+            node,
+            timestamp,
+            sim_time_ms: 15,
+            packet,
+        }
+    }
+
+    /// Splits a capture into `(block type, body)`, checking the two lengths of every block.
+    fn blocks(mut capture: &[u8]) -> Vec<(u32, &[u8])> {
+        let mut blocks = Vec::new();
+        while !capture.is_empty() {
+            let block_type = u32::from_le_bytes(capture[0..4].try_into().unwrap());
+            let length = u32::from_le_bytes(capture[4..8].try_into().unwrap()) as usize;
+            assert_eq!(length % 4, 0);
+            let trailer = u32::from_le_bytes(capture[length - 4..length].try_into().unwrap());
+            assert_eq!(trailer as usize, length);
+            blocks.push((block_type, &capture[8..length - 4]));
+            capture = &capture[length..];
+        }
+        blocks
+    }
+
+    #[test]
+    fn ack_is_encoded_big_endian() {
+        let ack = packet(vec![1, 2, 3], PacketType::Ack(Ack { fragment_index: 9 }));
+        assert_eq!(
+            encode_packet(&ack),
+            [
+                vec![ENCODING_VERSION, 1],
+                vec![1, 2, 3, 4, 5, 6, 7, 8],
+                vec![1, 3, 1, 2, 3],
+                vec![0, 0, 0, 0, 0, 0, 0, 9],
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn nack_ends_with_its_type_and_node() {
+        let nack = |nack_type| {
+            let nack = Nack {
+                fragment_index: 0,
+                nack_type,
+            };
+            encode_packet(&packet(vec![1, 2], PacketType::Nack(nack)))
+        };
+        assert!(nack(NackType::ErrorInRouting(5)).ends_with(&[0, 5]));
+        assert!(nack(NackType::DestinationIsDrone).ends_with(&[1, 0]));
+        assert!(nack(NackType::Dropped).ends_with(&[2, 0]));
+        assert!(nack(NackType::UnexpectedRecipient(7)).ends_with(&[3, 7]));
+        // Header of 12 bytes with 2 hops, fragment index, type and node
+        assert_eq!(nack(NackType::Dropped).len(), 12 + 2 + 8 + 2);
+    }
+
+    #[test]
+    fn fragment_carries_the_whole_data_block() {
+        let fragment = Fragment {
+            fragment_index: 1,
+            total_n_fragments: 3,
+            length: 2,
+            data: [0xAB; FRAGMENT_DSIZE],
+        };
+        let encoded = encode_packet(&packet(vec![1, 2], PacketType::MsgFragment(fragment)));
+        assert_eq!(encoded[1], 0);
+        assert_eq!(encoded.len(), 12 + 2 + 8 + 8 + 1 + FRAGMENT_DSIZE);
+        assert_eq!(encoded[14 + 16], 2);
+        assert!(encoded[14 + 17..].iter().all(|&byte| byte == 0xAB));
+    }
+
+    #[test]
+    fn flood_request_path_has_node_types() {
+        let request = FloodRequest {
+            flood_id: 4,
+            initiator_id: 1,
+            path_trace: vec![(1, NodeType::Client), (2, NodeType::Drone)],
+        };
+        let encoded = encode_packet(&packet(vec![], PacketType::FloodRequest(request)));
+        assert_eq!(encoded[1], 3);
+        assert_eq!(encoded[10..12], [1, 0]);
+        assert_eq!(encoded[12..], [0, 0, 0, 0, 0, 0, 0, 4, 1, 2, 1, 0, 2, 1]);
+    }
+
+    #[test]
+    fn empty_capture_has_only_the_section_header() {
+        let capture = write_capture([]);
+        assert_eq!(capture.len(), 32);
+        assert_eq!(capture[0..4], [0x0A, 0x0D, 0x0D, 0x0A]);
+        assert_eq!(capture[4..8], 32u32.to_le_bytes());
+        // Byte order magic, version 1.0, unspecified section length
+        assert_eq!(capture[8..12], [0x4D, 0x3C, 0x2B, 0x1A]);
+        assert_eq!(capture[12..16], [1, 0, 0, 0]);
+        assert_eq!(capture[16..24], [0xFF; 8]);
+    }
+
+    #[test]
+    fn one_interface_per_receiving_node() {
+        let ack = packet(vec![1, 2, 3], PacketType::Ack(Ack { fragment_index: 9 }));
+        let packets = [
+            captured(7, 0, ack.clone()),
+            captured(3, 0, ack.clone()),
+            captured(7, 0, ack),
+        ];
+        let capture = write_capture(&packets);
+        let blocks = blocks(&capture);
+
+        let types: Vec<u32> = blocks.iter().map(|(block_type, _)| *block_type).collect();
+        assert_eq!(
+            types,
+            [
+                SECTION_HEADER_BLOCK,
+                INTERFACE_DESCRIPTION_BLOCK,
+                INTERFACE_DESCRIPTION_BLOCK,
+                ENHANCED_PACKET_BLOCK,
+                ENHANCED_PACKET_BLOCK,
+                ENHANCED_PACKET_BLOCK,
+            ]
+        );
+        // Link type, reserved, snapshot length, then the name padded to 8 bytes
+        let (_, interface) = blocks[1];
+        assert_eq!(interface[0..2], LINK_TYPE.to_le_bytes());
+        assert_eq!(interface[8..12], [2, 0, 6, 0]);
+        assert_eq!(&interface[12..18], b"node-3");
+        assert_eq!(interface[18..20], [0, 0]);
+        assert_eq!(interface[20..25], [9, 0, 1, 0, TIMESTAMP_RESOLUTION]);
+        assert_eq!(interface.len(), 32);
+
+        // Interfaces are numbered by increasing node ID
+        let interfaces: Vec<&[u8]> = blocks[3..].iter().map(|(_, body)| &body[0..4]).collect();
+        assert_eq!(interfaces, [[1, 0, 0, 0], [0, 0, 0, 0], [1, 0, 0, 0]]);
+    }
+
+    #[test]
+    fn enhanced_packet_layout() {
+        let ack = packet(vec![1, 2, 3], PacketType::Ack(Ack { fragment_index: 9 }));
+        let data = encode_packet(&ack);
+        assert_eq!(data.len(), 23);
+
+        let timestamp = (5 << 32) | 6;
+        let capture = write_capture(&[captured(3, timestamp, ack)]);
+        let (block_type, body) = blocks(&capture)[2];
+        assert_eq!(block_type, ENHANCED_PACKET_BLOCK);
+        // Timestamp high and low words, captured and original lengths
+        assert_eq!(body[4..8], [5, 0, 0, 0]);
+        assert_eq!(body[8..12], [6, 0, 0, 0]);
+        assert_eq!(body[12..16], [23, 0, 0, 0]);
+        assert_eq!(body[16..20], [23, 0, 0, 0]);
+        assert_eq!(body[20..43], data[..]);
+        // The data is padded to 24 bytes, then comes the comment padded to 12 bytes
+        assert_eq!(body[43], 0);
+        assert_eq!(body[44..48], [1, 0, 11, 0]);
+        assert_eq!(&body[48..59], b"run 2 +15ms");
+        assert_eq!(body[59..], [0, 0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,50 @@
+use crate::simulation::sniffer::{CapturedPacket, Sniffer, MAX_CAPTURED_PACKETS};
+use crossbeam_channel::{unbounded, Sender};
+use log::debug;
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+impl Sniffer {
+    /// Returns a sender that delivers to `node_sender`, recording every packet on the way.
+    ///
+    /// The forwarder thread stops once every clone of the returned sender is dropped, or
+    /// once the node channel is disconnected.
+    pub fn tap(&self, node_id: NodeId, node_sender: Sender<Packet>) -> Sender<Packet> {
+        let (tap_sender, tap_receiver) = unbounded::<Packet>();
+        let pending = Arc::clone(&self.pending);
+
+        thread::spawn(move || {
+            for packet in tap_receiver.iter() {
+                pending
+                    .lock()
+                    .push((node_id, Instant::now(), packet.clone()));
+                if node_sender.send(packet).is_err() {
+                    break;
+                }
+            }
+            debug!("Sniffer forwarder for node {} stopped", node_id);
+        });
+
+        tap_sender
+    }
+
+    /// Takes the packets seen by the forwarders since the last call, in delivery order.
+    pub fn drain_pending(&self) -> Vec<(NodeId, Instant, Packet)> {
+        std::mem::take(&mut *self.pending.lock())
+    }
+
+    pub fn record(&mut self, packet: CapturedPacket) {
+        self.captured.push_back(packet);
+        if self.captured.len() > MAX_CAPTURED_PACKETS {
+            self.captured.pop_front();
+        }
+    }
+
+    /// Returns the packets captured during the run `run_id`, oldest first.
+    pub fn captured(&self, run_id: u64) -> impl Iterator<Item = &CapturedPacket> {
+        self.captured.iter().filter(move |p| p.run_id == run_id)
+    }
+}
@@ -1,8 +1,16 @@
 mod state_handler;
 
+use crate::simulation::clock::SimulationClock;
+use crate::simulation::controller::injector::InjectionTracker;
+use crate::simulation::events::EventLog;
+use crate::simulation::journal::Journal;
+use crate::simulation::listener::flood_analyzer::FloodAnalyzer;
+use crate::simulation::listener::journey_tracker::JourneyTracker;
+use crate::simulation::listener::session_tracker::SessionTracker;
 use crate::simulation::metrics::Metrics;
+use crate::simulation::runs::RunSummary;
+use crate::simulation::sniffer::Sniffer;
 use crate::simulation::topology::GraphState;
-use crate::utils::ControllerEvent;
 use common_utils::{HostCommand, HostEvent};
 use crossbeam_channel::{Receiver, Sender};
 use serde::{Deserialize, Serialize};
@@ -55,9 +63,39 @@ pub struct SimulationState {
     /// If validation should be strict
     strict_mode: bool,
 
+    /// If the inbound channels of the nodes should be tapped by the sniffer
+    sniffer_enabled: bool,
+
     // The metrics of the network
     metrics: Metrics,
 
-    /// A list of received messages for each node.
-    received_messages: Vec<ControllerEvent>,
+    /// Analytics of the flood discoveries
+    flood_analyzer: FloodAnalyzer,
+
+    /// Delivery tracking of the sessions
+    session_tracker: SessionTracker,
+
+    /// Hop-by-hop journeys of the routed packets
+    journey_tracker: JourneyTracker,
+
+    /// Batches injected by the simulation controller
+    injection_tracker: InjectionTracker,
+
+    /// The events received from the nodes.
+    event_log: EventLog,
+
+    /// The packets delivered to the nodes, when the sniffer is enabled.
+    sniffer: Sniffer,
+
+    /// Identifier of the current (or last) simulation run.
+    run_id: u64,
+
+    /// Run-relative time read by the metrics and the event log.
+    clock: SimulationClock,
+
+    /// Summaries of the previous runs, oldest first.
+    archived_runs: Vec<RunSummary>,
+
+    /// Undo and redo history of the topology edits.
+    journal: Journal,
 }
@@ -1,11 +1,20 @@
 use crate::error::NetworkError;
+use crate::simulation::clock::SimulationClock;
 use crate::simulation::controller::controller_commands::send_remove_sender_command;
+use crate::simulation::controller::injector::InjectionTracker;
+use crate::simulation::events::EventLog;
+use crate::simulation::journal::Journal;
+use crate::simulation::listener::flood_analyzer::FloodAnalyzer;
+use crate::simulation::listener::journey_tracker::JourneyTracker;
+use crate::simulation::listener::session_tracker::SessionTracker;
+use crate::simulation::runs::RunSummary;
+use crate::simulation::sniffer::Sniffer;
 use crate::simulation::state::{SimulationState, SimulationStatus};
 use crate::simulation::topology::validations::validate_graph;
-use crate::utils::ControllerEvent;
 use common_utils::{HostCommand, HostEvent};
 use crossbeam_channel::{Receiver, Sender};
 use log::{error, info};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::default::Default;
 use std::thread::JoinHandle;
@@ -32,8 +41,18 @@ impl SimulationState {
             graph: Default::default(),
             discovery_interval: None,
             strict_mode: false,
+            sniffer_enabled: false,
             metrics: Default::default(),
-            received_messages: vec![],
+            flood_analyzer: Default::default(),
+            session_tracker: Default::default(),
+            journey_tracker: Default::default(),
+            injection_tracker: Default::default(),
+            event_log: Default::default(),
+            sniffer: Default::default(),
+            run_id: 0,
+            clock: Default::default(),
+            archived_runs: vec![],
+            journal: Default::default(),
         }
     }
 
@@ -46,13 +65,18 @@ impl SimulationState {
         self.server_controller_channels.clear();
         self.graph = Default::default();
         self.metrics = Default::default();
-        self.received_messages.clear();
+        self.flood_analyzer = Default::default();
+        self.session_tracker = Default::default();
+        self.journey_tracker = Default::default();
+        self.injection_tracker = Default::default();
+        self.journal.discard_runtime_entries();
     }
 
     pub fn load_config_from_file(&mut self, path: &str) -> Result<(), NetworkError> {
         let config = crate::simulation::configs::configs_handler::get_config_from_file(path)?;
 
         self.initial_config = Some(config);
+        self.journal = Default::default();
         Ok(())
     }
 
@@ -147,6 +171,14 @@ impl SimulationState {
         self.strict_mode = strict;
     }
 
+    pub fn get_sniffer_enabled(&self) -> bool {
+        self.sniffer_enabled
+    }
+
+    pub fn set_sniffer_enabled(&mut self, enabled: bool) {
+        self.sniffer_enabled = enabled;
+    }
+
     pub fn get_config(&self) -> Option<&wg_2024::config::Config> {
         self.initial_config.as_ref()
     }
@@ -163,6 +195,19 @@ impl SimulationState {
         &mut self.graph
     }
 
+    /// Returns the graph of the running network, or the graph of the loaded configuration
+    /// when the network is not running.
+    pub fn get_topology(&self) -> Cow<'_, crate::simulation::topology::GraphState> {
+        match &self.initial_config {
+            Some(config) if self.status != SimulationStatus::Running => {
+                let mut graph = crate::simulation::topology::GraphState::new();
+                graph.build_graph(config);
+                Cow::Owned(graph)
+            }
+            _ => Cow::Borrowed(&self.graph),
+        }
+    }
+
     pub fn set_graph(&mut self, graph: crate::simulation::topology::GraphState) {
         self.graph = graph;
     }
@@ -175,11 +220,80 @@ impl SimulationState {
         &mut self.metrics
     }
 
-    pub fn get_received_messages(&self) -> &Vec<ControllerEvent> {
-        &self.received_messages
+    pub fn get_flood_analyzer(&self) -> &FloodAnalyzer {
+        &self.flood_analyzer
+    }
+
+    pub fn get_flood_analyzer_mut(&mut self) -> &mut FloodAnalyzer {
+        &mut self.flood_analyzer
+    }
+
+    pub fn get_session_tracker(&self) -> &SessionTracker {
+        &self.session_tracker
     }
-    pub fn get_received_messages_mut(&mut self) -> &mut Vec<ControllerEvent> {
-        &mut self.received_messages
+
+    pub fn get_session_tracker_mut(&mut self) -> &mut SessionTracker {
+        &mut self.session_tracker
+    }
+
+    pub fn get_journey_tracker(&self) -> &JourneyTracker {
+        &self.journey_tracker
+    }
+
+    pub fn get_journey_tracker_mut(&mut self) -> &mut JourneyTracker {
+        &mut self.journey_tracker
+    }
+
+    pub fn get_injection_tracker(&self) -> &InjectionTracker {
+        &self.injection_tracker
+    }
+
+    pub fn get_injection_tracker_mut(&mut self) -> &mut InjectionTracker {
+        &mut self.injection_tracker
+    }
+
+    pub fn get_event_log(&self) -> &EventLog {
+        &self.event_log
+    }
+
+    pub fn get_event_log_mut(&mut self) -> &mut EventLog {
+        &mut self.event_log
+    }
+
+    pub fn get_sniffer(&self) -> &Sniffer {
+        &self.sniffer
+    }
+
+    pub fn get_sniffer_mut(&mut self) -> &mut Sniffer {
+        &mut self.sniffer
+    }
+
+    pub fn get_run_id(&self) -> u64 {
+        self.run_id
+    }
+
+    pub fn get_clock(&self) -> &SimulationClock {
+        &self.clock
+    }
+
+    pub fn get_clock_mut(&mut self) -> &mut SimulationClock {
+        &mut self.clock
+    }
+
+    pub fn get_archived_runs(&self) -> &Vec<RunSummary> {
+        &self.archived_runs
+    }
+
+    pub fn get_archived_runs_mut(&mut self) -> &mut Vec<RunSummary> {
+        &mut self.archived_runs
+    }
+
+    pub fn get_journal(&self) -> &Journal {
+        &self.journal
+    }
+
+    pub fn get_journal_mut(&mut self) -> &mut Journal {
+        &mut self.journal
     }
 
     pub fn start_simulation(&mut self) -> Result<(), NetworkError> {
@@ -203,6 +317,10 @@ impl SimulationState {
         // Initialize the network
         crate::simulation::initializer::network_initializer::initialize_network(self)?;
 
+        self.run_id += 1;
+        self.clock.start();
+        self.event_log.start_run(self.run_id);
+
         Ok(())
     }
 
@@ -250,13 +368,15 @@ impl SimulationState {
             info!("Sent 'Stop' command to server {}", server_id);
         }
 
+        self.clock.stop();
+        self.archive_current_run();
+
         self.inter_node_channels.clear();
         self.drones_controller_channels.clear();
         self.client_controller_channels.clear();
         self.server_controller_channels.clear();
         self.graph = Default::default();
         self.metrics = Default::default();
-        self.received_messages.clear();
 
         for (node_id, handle) in self.node_threads.drain() {
             info!("Attempting to join thread for node {}", node_id);
@@ -0,0 +1,500 @@
+use crate::simulation::topology::validations::validate_graph;
+use crate::simulation::topology::{GraphState, NodeMetadata};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use wg_2024::network::NodeId;
+
+/// Capacity standing for an unbounded link in the flow networks.
+const UNBOUNDED: u32 = u32::MAX / 4;
+
+/// Critical nodes and links of the network.
+///
+/// Articulation points, bridges and the global connectivities are computed on the whole
+/// graph, as the validation does. The host-pair cuts only consider routes with drones as
+/// intermediate hops.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologyAnalysis {
+    /// Nodes whose removal disconnects the graph
+    pub articulation_points: Vec<NodeId>,
+    /// Links whose removal disconnects the graph
+    pub bridges: Vec<(NodeId, NodeId)>,
+    /// Minimum number of nodes to remove to disconnect the graph
+    pub vertex_connectivity: usize,
+    /// Minimum number of links to remove to disconnect the graph
+    pub edge_connectivity: usize,
+    pub host_pair_cuts: Vec<HostPairCut>,
+    /// Drones whose crash would be rejected by the validation
+    pub crash_blockers: Vec<CrashBlocker>,
+}
+
+/// The minimum cuts separating two hosts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostPairCut {
+    pub source: NodeId,
+    pub destination: NodeId,
+    /// Minimum number of drones whose crash separates the hosts, if they are not neighbors
+    pub min_vertex_cut: Option<usize>,
+    /// A set of drones of minimum size separating the hosts
+    pub vertex_cut: Vec<NodeId>,
+    /// Minimum number of links whose removal separates the hosts
+    pub min_edge_cut: usize,
+    /// A set of links of minimum size separating the hosts
+    pub edge_cut: Vec<(NodeId, NodeId)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashBlocker {
+    pub drone: NodeId,
+    /// The validation error the crash would raise
+    pub reason: String,
+}
+
+/// Analyzes the critical nodes and links of `graph`. `strict` is the validation mode the
+/// crashes are checked with.
+pub fn analyze_topology(graph: &GraphState, strict: bool) -> TopologyAnalysis {
+    let nodes = sorted_nodes(graph);
+    let (articulation_points, bridges) = articulation_points_and_bridges(graph, &nodes);
+
+    let mut host_pair_cuts = Vec::new();
+    let hosts: Vec<NodeId> = nodes
+        .iter()
+        .copied()
+        .filter(|&n| !is_drone(graph, n))
+        .collect();
+    for (i, &source) in hosts.iter().enumerate() {
+        for &destination in &hosts[i + 1..] {
+            host_pair_cuts.push(host_pair_cut(graph, &nodes, source, destination));
+        }
+    }
+
+    let crash_blockers = nodes
+        .iter()
+        .copied()
+        .filter(|&n| is_drone(graph, n))
+        .filter_map(|drone| {
+            let mut crashed = graph.clone();
+            crashed.crash_drone(drone).ok()?;
+            validate_graph(&crashed, strict)
+                .err()
+                .map(|err| CrashBlocker {
+                    drone,
+                    reason: err.to_string(),
+                })
+        })
+        .collect();
+
+    TopologyAnalysis {
+        articulation_points,
+        bridges,
+        vertex_connectivity: vertex_connectivity(graph, &nodes),
+        edge_connectivity: edge_connectivity(graph, &nodes),
+        host_pair_cuts,
+        crash_blockers,
+    }
+}
+
+fn sorted_nodes(graph: &GraphState) -> Vec<NodeId> {
+    let mut nodes: Vec<NodeId> = graph.adjacency.keys().copied().collect();
+    nodes.sort_unstable();
+    nodes
+}
+
+fn is_drone(graph: &GraphState, node_id: NodeId) -> bool {
+    matches!(graph.get_node_type(node_id), Some(NodeMetadata::Drone(_)))
+}
+
+fn are_neighbors(graph: &GraphState, a: NodeId, b: NodeId) -> bool {
+    graph.adjacency.get(&a).is_some_and(|n| n.contains(&b))
+}
+
+/// Tarjan's algorithm: articulation points and bridges from the DFS discovery times and
+/// low-links.
+fn articulation_points_and_bridges(
+    graph: &GraphState,
+    nodes: &[NodeId],
+) -> (Vec<NodeId>, Vec<(NodeId, NodeId)>) {
+    struct Search<'a> {
+        graph: &'a GraphState,
+        time: usize,
+        discovery: HashMap<NodeId, usize>,
+        low: HashMap<NodeId, usize>,
+        points: Vec<NodeId>,
+        bridges: Vec<(NodeId, NodeId)>,
+    }
+
+    impl Search<'_> {
+        fn visit(&mut self, node: NodeId, parent: Option<NodeId>) {
+            self.time += 1;
+            self.discovery.insert(node, self.time);
+            self.low.insert(node, self.time);
+            let mut children = 0;
+            let mut is_point = false;
+
+            for next in self.graph.get_neighbors(node) {
+                if Some(next) == parent {
+                    continue;
+                }
+                if let Some(&discovered) = self.discovery.get(&next) {
+                    let low = self.low[&node].min(discovered);
+                    self.low.insert(node, low);
+                    continue;
+                }
+                children += 1;
+                self.visit(next, Some(node));
+                let low = self.low[&node].min(self.low[&next]);
+                self.low.insert(node, low);
+                if parent.is_some() && self.low[&next] >= self.discovery[&node] {
+                    is_point = true;
+                }
+                if self.low[&next] > self.discovery[&node] {
+                    self.bridges.push((node.min(next), node.max(next)));
+                }
+            }
+
+            if is_point || (parent.is_none() && children > 1) {
+                self.points.push(node);
+            }
+        }
+    }
+
+    let mut search = Search {
+        graph,
+        time: 0,
+        discovery: HashMap::new(),
+        low: HashMap::new(),
+        points: Vec::new(),
+        bridges: Vec::new(),
+    };
+    for &node in nodes {
+        if !search.discovery.contains_key(&node) {
+            search.visit(node, None);
+        }
+    }
+    search.points.sort_unstable();
+    search.bridges.sort_unstable();
+    (search.points, search.bridges)
+}
+
+/// Minimum over the pairs of non-neighboring nodes of the number of node-disjoint paths.
+fn vertex_connectivity(graph: &GraphState, nodes: &[NodeId]) -> usize {
+    if nodes.len() < 2 {
+        return 0;
+    }
+    let index = node_index(nodes);
+    let mut connectivity = nodes.len() - 1;
+    for (i, &a) in nodes.iter().enumerate() {
+        for &b in &nodes[i + 1..] {
+            if are_neighbors(graph, a, b) {
+                continue;
+            }
+            let mut network = vertex_network(graph, nodes, |_| true);
+            let flow = network.max_flow(2 * index[&a] + 1, 2 * index[&b]);
+            connectivity = connectivity.min(flow as usize);
+        }
+    }
+    connectivity
+}
+
+/// Minimum over the nodes of the number of link-disjoint paths from the first node.
+fn edge_connectivity(graph: &GraphState, nodes: &[NodeId]) -> usize {
+    let Some((&first, others)) = nodes.split_first() else {
+        return 0;
+    };
+    let index = node_index(nodes);
+    others
+        .iter()
+        .map(|&other| {
+            let mut network = edge_network(graph, nodes, |_| true);
+            network.max_flow(index[&first], index[&other]) as usize
+        })
+        .min()
+        .unwrap_or(0)
+}
+
+/// Minimum cuts between two hosts, over the routes whose intermediate hops are drones.
+fn host_pair_cut(
+    graph: &GraphState,
+    nodes: &[NodeId],
+    source: NodeId,
+    destination: NodeId,
+) -> HostPairCut {
+    let index = node_index(nodes);
+    let (s, t) = (index[&source], index[&destination]);
+    let routable = |n: NodeId| n == source || n == destination || is_drone(graph, n);
+
+    let (min_vertex_cut, vertex_cut) = if are_neighbors(graph, source, destination) {
+        (None, Vec::new())
+    } else {
+        let mut network = vertex_network(graph, nodes, routable);
+        let flow = network.max_flow(2 * s + 1, 2 * t);
+        let reachable = network.reachable_from(2 * s + 1);
+        let cut = nodes
+            .iter()
+            .enumerate()
+            .filter(|&(i, &n)| {
+                n != source && n != destination && reachable[2 * i] && !reachable[2 * i + 1]
+            })
+            .map(|(_, &n)| n)
+            .collect();
+        (Some(flow as usize), cut)
+    };
+
+    let mut network = edge_network(graph, nodes, routable);
+    let min_edge_cut = network.max_flow(s, t) as usize;
+    let reachable = network.reachable_from(s);
+    let mut edge_cut = Vec::new();
+    for (i, &a) in nodes.iter().enumerate() {
+        for (j, &b) in nodes.iter().enumerate() {
+            // Every link from the source side to the sink side of the cut is saturated
+            if reachable[i]
+                && !reachable[j]
+                && routable(a)
+                && routable(b)
+                && are_neighbors(graph, a, b)
+            {
+                edge_cut.push((a.min(b), a.max(b)));
+            }
+        }
+    }
+    edge_cut.sort_unstable();
+    edge_cut.dedup();
+
+    HostPairCut {
+        source,
+        destination,
+        min_vertex_cut,
+        vertex_cut,
+        min_edge_cut,
+        edge_cut,
+    }
+}
+
+fn node_index(nodes: &[NodeId]) -> HashMap<NodeId, usize> {
+    nodes.iter().enumerate().map(|(i, &n)| (n, i)).collect()
+}
+
+/// Flow network where each node `i` is split into `2i` (in) and `2i + 1` (out), linked with a
+/// capacity of 1, so that the flow counts node-disjoint paths. Only the nodes accepted by
+/// `allowed` are linked.
+fn vertex_network(
+    graph: &GraphState,
+    nodes: &[NodeId],
+    allowed: impl Fn(NodeId) -> bool,
+) -> FlowNetwork {
+    let index = node_index(nodes);
+    let mut network = FlowNetwork::new(2 * nodes.len());
+    for (i, &node) in nodes.iter().enumerate() {
+        if !allowed(node) {
+            continue;
+        }
+        network.capacity[2 * i][2 * i + 1] = 1;
+        for next in graph.get_neighbors(node) {
+            if let Some(&j) = index.get(&next).filter(|_| allowed(next)) {
+                network.capacity[2 * i + 1][2 * j] = UNBOUNDED;
+            }
+        }
+    }
+    network
+}
+
+/// Flow network with a capacity of 1 on each link, so that the flow counts link-disjoint
+/// paths. Only the nodes accepted by `allowed` are linked.
+fn edge_network(
+    graph: &GraphState,
+    nodes: &[NodeId],
+    allowed: impl Fn(NodeId) -> bool,
+) -> FlowNetwork {
+    let index = node_index(nodes);
+    let mut network = FlowNetwork::new(nodes.len());
+    for (i, &node) in nodes.iter().enumerate() {
+        if !allowed(node) {
+            continue;
+        }
+        for next in graph.get_neighbors(node) {
+            if let Some(&j) = index.get(&next).filter(|_| allowed(next)) {
+                network.capacity[i][j] = 1;
+            }
+        }
+    }
+    network
+}
+
+/// Dense residual network for Edmonds-Karp. The graphs of the simulation have at most a
+/// few hundred nodes.
+struct FlowNetwork {
+    capacity: Vec<Vec<u32>>,
+}
+
+impl FlowNetwork {
+    fn new(size: usize) -> Self {
+        Self {
+            capacity: vec![vec![0; size]; size],
+        }
+    }
+
+    /// Pushes the maximum flow from `source` to `sink`, leaving the residual capacities.
+    fn max_flow(&mut self, source: usize, sink: usize) -> u32 {
+        let size = self.capacity.len();
+        let mut flow = 0;
+        loop {
+            let mut previous = vec![usize::MAX; size];
+            previous[source] = source;
+            let mut queue = VecDeque::from([source]);
+            while let Some(node) = queue.pop_front() {
+                for (next, &capacity) in self.capacity[node].iter().enumerate() {
+                    if previous[next] == usize::MAX && capacity > 0 {
+                        previous[next] = node;
+                        queue.push_back(next);
+                    }
+                }
+            }
+            if previous[sink] == usize::MAX {
+                return flow;
+            }
+
+            let mut bottleneck = u32::MAX;
+            let mut node = sink;
+            while node != source {
+                let prev = previous[node];
+                bottleneck = bottleneck.min(self.capacity[prev][node]);
+                node = prev;
+            }
+            let mut node = sink;
+            while node != source {
+                let prev = previous[node];
+                self.capacity[prev][node] -= bottleneck;
+                self.capacity[node][prev] += bottleneck;
+                node = prev;
+            }
+            flow += bottleneck;
+        }
+    }
+
+    /// Nodes reachable from `source` in the residual network: the source side of a minimum cut.
+    fn reachable_from(&self, source: usize) -> Vec<bool> {
+        let mut reachable = vec![false; self.capacity.len()];
+        reachable[source] = true;
+        let mut queue = VecDeque::from([source]);
+        while let Some(node) = queue.pop_front() {
+            for (next, &capacity) in self.capacity[node].iter().enumerate() {
+                if !reachable[next] && capacity > 0 {
+                    reachable[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        reachable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::topology::test_graph;
+
+    /// A triangle of drones with a host hanging on each side:
+    ///
+    /// ```text
+    /// 1 -- 10 -- 11 -- 2
+    ///        \  /
+    ///         12
+    /// ```
+    fn triangle() -> GraphState {
+        test_graph(
+            &[(10, &[1, 11, 12]), (11, &[10, 12, 2]), (12, &[10, 11])],
+            &[(1, &[10])],
+            &[(2, &[11])],
+        )
+    }
+
+    /// A cycle of six nodes: two disjoint routes between the hosts.
+    ///
+    /// ```text
+    ///   10 -- 12
+    ///  /        \
+    /// 1          2
+    ///  \        /
+    ///   11 -- 13
+    /// ```
+    fn cycle() -> GraphState {
+        test_graph(
+            &[
+                (10, &[1, 12]),
+                (11, &[1, 13]),
+                (12, &[10, 2]),
+                (13, &[11, 2]),
+            ],
+            &[(1, &[10, 11])],
+            &[(2, &[12, 13])],
+        )
+    }
+
+    #[test]
+    fn tarjan_finds_the_cut_nodes_and_links() {
+        let graph = triangle();
+        let (points, bridges) = articulation_points_and_bridges(&graph, &sorted_nodes(&graph));
+        assert_eq!(points, [10, 11]);
+        assert_eq!(bridges, [(1, 10), (2, 11)]);
+
+        let graph = cycle();
+        let (points, bridges) = articulation_points_and_bridges(&graph, &sorted_nodes(&graph));
+        assert!(points.is_empty());
+        assert!(bridges.is_empty());
+    }
+
+    #[test]
+    fn connectivity_counts_disjoint_paths() {
+        let graph = triangle();
+        let nodes = sorted_nodes(&graph);
+        assert_eq!(vertex_connectivity(&graph, &nodes), 1);
+        assert_eq!(edge_connectivity(&graph, &nodes), 1);
+
+        let graph = cycle();
+        let nodes = sorted_nodes(&graph);
+        assert_eq!(vertex_connectivity(&graph, &nodes), 2);
+        assert_eq!(edge_connectivity(&graph, &nodes), 2);
+    }
+
+    #[test]
+    fn host_pair_cut_through_a_single_drone() {
+        let graph = triangle();
+        let cut = host_pair_cut(&graph, &sorted_nodes(&graph), 1, 2);
+        assert_eq!(cut.min_vertex_cut, Some(1));
+        assert_eq!(cut.vertex_cut, [10]);
+        assert_eq!(cut.min_edge_cut, 1);
+        assert_eq!(cut.edge_cut, [(1, 10)]);
+    }
+
+    #[test]
+    fn host_pair_cut_of_two_disjoint_routes() {
+        let graph = cycle();
+        let cut = host_pair_cut(&graph, &sorted_nodes(&graph), 1, 2);
+        assert_eq!(cut.min_vertex_cut, Some(2));
+        assert_eq!(cut.vertex_cut, [10, 11]);
+        assert_eq!(cut.min_edge_cut, 2);
+        assert_eq!(cut.edge_cut, [(1, 10), (1, 11)]);
+    }
+
+    #[test]
+    fn max_flow_leaves_the_residual_network() {
+        // 0 -> 1 -> 3 and 0 -> 2 -> 3, with a bottleneck of 1 on 2 -> 3
+        let mut network = FlowNetwork::new(4);
+        network.capacity[0][1] = 2;
+        network.capacity[1][3] = 2;
+        network.capacity[0][2] = 3;
+        network.capacity[2][3] = 1;
+        assert_eq!(network.max_flow(0, 3), 3);
+        assert_eq!(network.reachable_from(0), [true, false, true, false]);
+        assert_eq!(network.max_flow(0, 3), 0);
+    }
+
+    #[test]
+    fn analysis_reports_the_drones_that_cannot_crash() {
+        let analysis = analyze_topology(&triangle(), false);
+        assert_eq!(analysis.articulation_points, [10, 11]);
+        assert_eq!(analysis.host_pair_cuts.len(), 1);
+        let blockers: Vec<NodeId> = analysis.crash_blockers.iter().map(|b| b.drone).collect();
+        assert!(blockers.contains(&10));
+        assert!(blockers.contains(&11));
+    }
+}
@@ -0,0 +1,34 @@
+use crate::error::NetworkError;
+use crate::simulation::topology::GraphState;
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+/// A change to the topology of the network, as accepted by the topology commands.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum TopologyEdit {
+    CrashDrone { drone_id: NodeId },
+    RemoveEdge { node1_id: NodeId, node2_id: NodeId },
+    AddEdge { node1_id: NodeId, node2_id: NodeId },
+    AddDrone { neighbors: Vec<NodeId>, pdr: u32 },
+}
+
+impl TopologyEdit {
+    /// Applies the edit to `graph` without validating the result. Returns the ID given to
+    /// the drone added by `AddDrone`.
+    pub fn apply(&self, graph: &mut GraphState) -> Result<Option<NodeId>, NetworkError> {
+        match self {
+            TopologyEdit::CrashDrone { drone_id } => graph.crash_drone(*drone_id)?,
+            TopologyEdit::RemoveEdge { node1_id, node2_id } => {
+                graph.remove_edge(*node1_id, *node2_id)?
+            }
+            TopologyEdit::AddEdge { node1_id, node2_id } => graph.add_edge(*node1_id, *node2_id)?,
+            TopologyEdit::AddDrone { neighbors, pdr } => {
+                let node_id = graph.get_next_node_id();
+                graph.add_drone(node_id, neighbors.clone(), *pdr)?;
+                return Ok(Some(node_id));
+            }
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,121 @@
+use crate::error::NetworkError;
+use crate::simulation::listener::journey_tracker::ObservedRoute;
+use crate::simulation::topology::edits::TopologyEdit;
+use crate::simulation::topology::routing::shortest_path;
+use crate::simulation::topology::validations::validate_graph;
+use crate::simulation::topology::{GraphState, NodeMetadata};
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+/// What would happen if a list of edits was applied. Nothing is applied to compute it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImpactReport {
+    /// Whether the validation accepts the resulting graph
+    pub valid: bool,
+    /// The error raised by the validation, if any
+    pub validation_error: Option<String>,
+    /// The drone the edits would add, for `AddDrone`
+    pub added_drone: Option<NodeId>,
+    /// Host pairs with a route before the edits, and none after
+    pub lost_host_pairs: Vec<(NodeId, NodeId)>,
+    /// Host pairs whose shortest route changes length
+    pub path_changes: Vec<PathLengthChange>,
+    /// Observed routes that go through a removed node or link
+    pub broken_routes: Vec<BrokenRoute>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathLengthChange {
+    pub source: NodeId,
+    pub destination: NodeId,
+    /// Number of hops of the shortest route, `None` when there is no route
+    pub before: Option<usize>,
+    pub after: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrokenRoute {
+    #[serde(flatten)]
+    pub route: ObservedRoute,
+    /// The first missing node or link of the route
+    pub reason: String,
+}
+
+/// Computes the impact of applying `edits` to `graph`, in order.
+///
+/// Fails if an edit cannot be applied at all, e.g. because it removes a link that does not
+/// exist. A graph rejected by the validation still gets an impact report.
+pub fn preview(
+    graph: &GraphState,
+    strict: bool,
+    edits: &[TopologyEdit],
+    observed_routes: &[ObservedRoute],
+) -> Result<ImpactReport, NetworkError> {
+    let mut new_graph = graph.clone();
+    let mut added_drone = None;
+    for edit in edits {
+        if let Some(node_id) = edit.apply(&mut new_graph)? {
+            added_drone = Some(node_id);
+        }
+    }
+    let validation_error = validate_graph(&new_graph, strict)
+        .err()
+        .map(|err| err.to_string());
+
+    let mut hosts: Vec<NodeId> = graph
+        .get_nodes_info()
+        .into_iter()
+        .filter(|(_, info)| matches!(info, NodeMetadata::Client | NodeMetadata::Server))
+        .map(|(id, _)| id)
+        .collect();
+    hosts.sort_unstable();
+
+    let mut lost_host_pairs = Vec::new();
+    let mut path_changes = Vec::new();
+    for (i, &source) in hosts.iter().enumerate() {
+        for &destination in &hosts[i + 1..] {
+            let before = shortest_path(graph, source, destination).map(|p| p.len() - 1);
+            let after = shortest_path(&new_graph, source, destination).map(|p| p.len() - 1);
+            if before.is_some() && after.is_none() {
+                lost_host_pairs.push((source, destination));
+            }
+            if before != after {
+                path_changes.push(PathLengthChange {
+                    source,
+                    destination,
+                    before,
+                    after,
+                });
+            }
+        }
+    }
+
+    let broken_routes = observed_routes
+        .iter()
+        .filter_map(|route| {
+            missing_part(&new_graph, &route.hops).map(|reason| BrokenRoute {
+                route: route.clone(),
+                reason,
+            })
+        })
+        .collect();
+
+    Ok(ImpactReport {
+        valid: validation_error.is_none(),
+        validation_error,
+        added_drone,
+        lost_host_pairs,
+        path_changes,
+        broken_routes,
+    })
+}
+
+/// Describes the first node or link of `hops` missing from `graph`, if any.
+fn missing_part(graph: &GraphState, hops: &[NodeId]) -> Option<String> {
+    if let Some(node) = hops.iter().find(|&&n| graph.get_node_type(n).is_none()) {
+        return Some(format!("Node {} is missing", node));
+    }
+    hops.windows(2)
+        .find(|pair| !graph.get_neighbors(pair[0]).contains(&pair[1]))
+        .map(|pair| format!("Link {} - {} is missing", pair[0], pair[1]))
+}
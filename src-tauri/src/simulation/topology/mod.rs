@@ -1,6 +1,11 @@
 use std::collections::HashMap;
 use wg_2024::network::NodeId;
 
+pub mod connectivity;
+pub mod edits;
+pub mod impact;
+pub mod reliability;
+pub mod routing;
 pub mod topology_handler;
 pub mod validations;
 
@@ -38,3 +43,42 @@ impl DroneMetadata {
         self.node_group.clone()
     }
 }
+
+/// Builds the graph of a configuration made of `(id, neighbors)` drones, clients and servers.
+/// The drones never drop packets.
+#[cfg(test)]
+pub fn test_graph(
+    drones: &[(NodeId, &[NodeId])],
+    clients: &[(NodeId, &[NodeId])],
+    servers: &[(NodeId, &[NodeId])],
+) -> GraphState {
+    use wg_2024::config::{Client, Config, Drone, Server};
+
+    let config = Config {
+        drone: drones
+            .iter()
+            .map(|&(id, neighbors)| Drone {
+                id,
+                connected_node_ids: neighbors.to_vec(),
+                pdr: 0.0,
+            })
+            .collect(),
+        client: clients
+            .iter()
+            .map(|&(id, neighbors)| Client {
+                id,
+                connected_drone_ids: neighbors.to_vec(),
+            })
+            .collect(),
+        server: servers
+            .iter()
+            .map(|&(id, neighbors)| Server {
+                id,
+                connected_drone_ids: neighbors.to_vec(),
+            })
+            .collect(),
+    };
+    let mut graph = GraphState::new();
+    graph.build_graph(&config);
+    graph
+}
@@ -0,0 +1,344 @@
+use crate::error::NetworkError;
+use crate::simulation::metrics::Metrics;
+use crate::simulation::topology::routing::next_hops;
+use crate::simulation::topology::{GraphState, NodeMetadata};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use wg_2024::network::NodeId;
+
+/// Where the packet drop rate of the drones is read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PdrSource {
+    /// The PDR set in the configuration or by `set_pdr`
+    #[default]
+    Configured,
+    /// The drop rate measured over the recent fragments, or the configured PDR for the drones
+    /// that have not forwarded any fragment yet
+    Measured,
+}
+
+/// Delivery probabilities of a route.
+///
+/// Drones only drop fragments, so Acks, NACKs and flood packets always go through: the round
+/// trip of a fragment is as reliable as the fragment itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReliabilityEstimate {
+    /// Probability that a single fragment reaches the destination
+    pub fragment: f64,
+    /// Probability that every fragment of the message reaches the destination at the first attempt
+    pub message: f64,
+    /// Probability that a fragment reaches the destination and its Ack comes back
+    pub round_trip: f64,
+    /// Expected number of transmissions of each fragment when dropped fragments are sent again
+    pub expected_transmissions: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteReliability {
+    pub hops: Vec<NodeId>,
+    /// Number of fragments of the message
+    pub fragments: u64,
+    pub configured: ReliabilityEstimate,
+    pub measured: ReliabilityEstimate,
+    /// Drones on the route without a measured drop rate, estimated with their configured PDR
+    pub unmeasured_drones: Vec<NodeId>,
+}
+
+/// Returns the drop rate of the drone `node_id` according to `source`, 0 for hosts.
+pub fn drop_rate(graph: &GraphState, metrics: &Metrics, node_id: NodeId, source: PdrSource) -> f64 {
+    let Some(NodeMetadata::Drone(drone)) = graph.get_node_type(node_id) else {
+        return 0.0;
+    };
+    let measured = metrics
+        .drone_metrics
+        .get(&node_id)
+        .filter(|m| !m.rolling_window.is_empty())
+        .map(|m| m.current_pdr);
+    match (source, measured) {
+        (PdrSource::Measured, Some(pdr)) => pdr as f64,
+        _ => drone.get_pdr() as f64,
+    }
+}
+
+/// Estimates the reliability of `hops` for a message of `fragments` fragments.
+pub fn route_reliability(
+    graph: &GraphState,
+    metrics: &Metrics,
+    hops: Vec<NodeId>,
+    fragments: u64,
+) -> Result<RouteReliability, NetworkError> {
+    validate_route(graph, &hops)?;
+
+    let drones = &hops[1..hops.len() - 1];
+    let unmeasured_drones = drones
+        .iter()
+        .copied()
+        .filter(|id| {
+            metrics
+                .drone_metrics
+                .get(id)
+                .is_none_or(|m| m.rolling_window.is_empty())
+        })
+        .collect();
+
+    let estimate = |source: PdrSource| {
+        let fragment: f64 = drones
+            .iter()
+            .map(|&id| 1.0 - drop_rate(graph, metrics, id, source))
+            .product();
+        ReliabilityEstimate {
+            fragment,
+            message: fragment.powi(fragments.min(i32::MAX as u64) as i32),
+            round_trip: fragment,
+            expected_transmissions: if fragment > 0.0 {
+                1.0 / fragment
+            } else {
+                f64::INFINITY
+            },
+        }
+    };
+
+    Ok(RouteReliability {
+        configured: estimate(PdrSource::Configured),
+        measured: estimate(PdrSource::Measured),
+        hops,
+        fragments,
+        unmeasured_drones,
+    })
+}
+
+/// Returns the route from `source` to `destination` with the highest probability of
+/// delivering a fragment, if any.
+///
+/// Dijkstra on the drones weighted with `-ln(1 - pdr)`: the lightest route is the one whose
+/// product of success probabilities is the highest. Drones dropping every fragment are avoided.
+pub fn most_reliable_route(
+    graph: &GraphState,
+    metrics: &Metrics,
+    source: NodeId,
+    destination: NodeId,
+    pdr_source: PdrSource,
+) -> Option<Vec<NodeId>> {
+    let mut cost: HashMap<NodeId, f64> = HashMap::from([(source, 0.0)]);
+    let mut previous: HashMap<NodeId, NodeId> = HashMap::new();
+    let mut heap = BinaryHeap::from([Candidate {
+        cost: 0.0,
+        hops: 0,
+        node: source,
+    }]);
+
+    while let Some(Candidate {
+        cost: c,
+        hops,
+        node,
+    }) = heap.pop()
+    {
+        if node == destination {
+            let mut path = vec![destination];
+            let mut current = destination;
+            while let Some(&prev) = previous.get(&current) {
+                path.push(prev);
+                current = prev;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if cost.get(&node).is_some_and(|&best| c > best) {
+            continue;
+        }
+        // Only the source and the drones forward packets
+        if node != source && !matches!(graph.get_node_type(node), Some(NodeMetadata::Drone(_))) {
+            continue;
+        }
+
+        for next in next_hops(graph, node, destination) {
+            let success = 1.0 - drop_rate(graph, metrics, next, pdr_source);
+            if success <= 0.0 {
+                continue;
+            }
+            let next_cost = c - success.ln();
+            if cost.get(&next).is_none_or(|&best| next_cost < best) {
+                cost.insert(next, next_cost);
+                previous.insert(next, node);
+                heap.push(Candidate {
+                    cost: next_cost,
+                    hops: hops + 1,
+                    node: next,
+                });
+            }
+        }
+    }
+    None
+}
+
+/// Ensures `hops` goes from a host to another host through neighboring drones.
+fn validate_route(graph: &GraphState, hops: &[NodeId]) -> Result<(), NetworkError> {
+    if hops.len() < 2 {
+        return Err(NetworkError::ValidationError(
+            "A route needs at least a source and a destination".into(),
+        ));
+    }
+    for (position, &node) in hops.iter().enumerate() {
+        let is_endpoint = position == 0 || position == hops.len() - 1;
+        match graph.get_node_type(node) {
+            None => return Err(NetworkError::NodeNotFound(node.to_string())),
+            Some(NodeMetadata::Drone(_)) if is_endpoint => {
+                return Err(NetworkError::ValidationError(format!(
+                    "The route must start and end at hosts, {} is a drone",
+                    node
+                )))
+            }
+            Some(NodeMetadata::Client) | Some(NodeMetadata::Server) if !is_endpoint => {
+                return Err(NetworkError::ValidationError(format!(
+                    "Host {} cannot forward packets",
+                    node
+                )))
+            }
+            _ => {}
+        }
+    }
+    for pair in hops.windows(2) {
+        if !graph.get_neighbors(pair[0]).contains(&pair[1]) {
+            return Err(NetworkError::EdgeNotFound(pair[0], pair[1]));
+        }
+    }
+    Ok(())
+}
+
+/// Entry of the Dijkstra queue, ordered so that the lowest cost is popped first, then the
+/// fewest hops.
+struct Candidate {
+    cost: f64,
+    hops: usize,
+    node: NodeId,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| other.hops.cmp(&self.hops))
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::topology::test_graph;
+
+    /// Client 1 and server 2, linked by the drone 10 or by the drones 11 and 12:
+    ///
+    /// ```text
+    /// 1 -- 10 -- 2
+    /// |          |
+    /// 11 ------- 12
+    /// ```
+    fn two_routes(pdrs: [(NodeId, u8); 3]) -> GraphState {
+        let mut graph = test_graph(
+            &[(10, &[1, 2]), (11, &[1, 12]), (12, &[11, 2])],
+            &[(1, &[10, 11])],
+            &[(2, &[10, 12])],
+        );
+        for (drone_id, pdr) in pdrs {
+            graph.set_pdr(drone_id, pdr).unwrap();
+        }
+        graph
+    }
+
+    /// Client 1 and server 2, only linked by the drone 10.
+    fn graph_without_detour(pdr: u8) -> GraphState {
+        let mut graph = test_graph(&[(10, &[1, 2])], &[(1, &[10])], &[(2, &[10])]);
+        graph.set_pdr(10, pdr).unwrap();
+        graph
+    }
+
+    fn most_reliable(graph: &GraphState) -> Option<Vec<NodeId>> {
+        most_reliable_route(graph, &Metrics::default(), 1, 2, PdrSource::Configured)
+    }
+
+    #[test]
+    fn drones_dropping_everything_are_avoided() {
+        let graph = two_routes([(10, 100), (11, 0), (12, 0)]);
+        assert_eq!(most_reliable(&graph), Some(vec![1, 11, 12, 2]));
+
+        let graph = graph_without_detour(100);
+        assert_eq!(most_reliable(&graph), None);
+    }
+
+    #[test]
+    fn highest_success_product_wins_over_fewest_hops() {
+        // 0.9 * 0.9 = 0.81 > 0.5
+        let graph = two_routes([(10, 50), (11, 10), (12, 10)]);
+        assert_eq!(most_reliable(&graph), Some(vec![1, 11, 12, 2]));
+        // 0.9 > 0.81
+        let graph = two_routes([(10, 10), (11, 10), (12, 10)]);
+        assert_eq!(most_reliable(&graph), Some(vec![1, 10, 2]));
+    }
+
+    #[test]
+    fn equally_reliable_routes_prefer_fewer_hops() {
+        let graph = two_routes([(10, 0), (11, 0), (12, 0)]);
+        assert_eq!(most_reliable(&graph), Some(vec![1, 10, 2]));
+    }
+
+    #[test]
+    fn reliability_is_the_product_of_the_success_rates() {
+        let graph = two_routes([(10, 0), (11, 10), (12, 10)]);
+        let reliability =
+            route_reliability(&graph, &Metrics::default(), vec![1, 11, 12, 2], 2).unwrap();
+        let estimate = reliability.configured;
+        assert!((estimate.fragment - 0.81).abs() < 1e-6);
+        assert!((estimate.message - 0.6561).abs() < 1e-6);
+        assert!((estimate.round_trip - 0.81).abs() < 1e-6);
+        assert!((estimate.expected_transmissions - 1.0 / 0.81).abs() < 1e-6);
+        // Without measurements, the configured PDRs are used
+        assert!((reliability.measured.fragment - 0.81).abs() < 1e-6);
+        assert_eq!(reliability.unmeasured_drones, [11, 12]);
+    }
+
+    #[test]
+    fn invalid_routes_are_rejected() {
+        let graph = two_routes([(10, 0), (11, 0), (12, 0)]);
+        let reliability =
+            |hops: Vec<NodeId>| route_reliability(&graph, &Metrics::default(), hops, 1);
+
+        assert!(matches!(
+            reliability(vec![1]),
+            Err(NetworkError::ValidationError(_))
+        ));
+        assert!(matches!(
+            reliability(vec![1, 99, 2]),
+            Err(NetworkError::NodeNotFound(_))
+        ));
+        // Drones cannot be endpoints, hosts cannot forward
+        assert!(matches!(
+            reliability(vec![10, 2]),
+            Err(NetworkError::ValidationError(_))
+        ));
+        assert!(matches!(
+            reliability(vec![1, 2, 10]),
+            Err(NetworkError::ValidationError(_))
+        ));
+        assert!(matches!(
+            reliability(vec![1, 11, 2]),
+            Err(NetworkError::EdgeNotFound(11, 2))
+        ));
+    }
+}
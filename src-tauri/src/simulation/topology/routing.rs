@@ -0,0 +1,280 @@
+use crate::error::NetworkError;
+use crate::simulation::topology::{GraphState, NodeMetadata};
+use std::collections::{HashMap, HashSet, VecDeque};
+use wg_2024::network::NodeId;
+
+/// Maximum number of paths returned by `simple_paths`, to bound the enumeration on dense graphs.
+pub const MAX_SIMPLE_PATHS: usize = 1_000;
+
+/// Ensures `node_id` is a client or a server, the only nodes a route can start or end at.
+pub fn check_host(graph: &GraphState, node_id: NodeId) -> Result<(), NetworkError> {
+    match graph.get_node_type(node_id) {
+        Some(NodeMetadata::Client) | Some(NodeMetadata::Server) => Ok(()),
+        Some(NodeMetadata::Drone(_)) => Err(NetworkError::InvalidOperation(format!(
+            "Routes start and end at hosts, {} is a drone",
+            node_id
+        ))),
+        None => Err(NetworkError::NodeNotFound(node_id.to_string())),
+    }
+}
+
+/// Returns the neighbors of `node_id` a route to `destination` can continue with: the drones,
+/// and `destination` itself. Hosts cannot forward packets.
+pub fn next_hops(graph: &GraphState, node_id: NodeId, destination: NodeId) -> Vec<NodeId> {
+    let mut neighbors: Vec<NodeId> = graph
+        .get_neighbors(node_id)
+        .into_iter()
+        .filter(|&n| {
+            n == destination || matches!(graph.get_node_type(n), Some(NodeMetadata::Drone(_)))
+        })
+        .collect();
+    // Sorted, so that the routes do not depend on the order of the adjacency lists
+    neighbors.sort_unstable();
+    neighbors.dedup();
+    neighbors
+}
+
+/// Returns a route with the fewest hops from `source` to `destination`, if any.
+pub fn shortest_path(
+    graph: &GraphState,
+    source: NodeId,
+    destination: NodeId,
+) -> Option<Vec<NodeId>> {
+    constrained_shortest_path(graph, source, destination, &HashSet::new(), &HashSet::new())
+}
+
+/// Returns up to `k` loopless routes from `source` to `destination`, by increasing number of
+/// hops (Yen's algorithm).
+pub fn k_shortest_paths(
+    graph: &GraphState,
+    source: NodeId,
+    destination: NodeId,
+    k: usize,
+) -> Vec<Vec<NodeId>> {
+    let mut paths: Vec<Vec<NodeId>> = Vec::new();
+    if k == 0 {
+        return paths;
+    }
+    let Some(first) = shortest_path(graph, source, destination) else {
+        return paths;
+    };
+    paths.push(first);
+    let mut candidates: Vec<Vec<NodeId>> = Vec::new();
+
+    while paths.len() < k {
+        let previous = paths.last().unwrap().clone();
+        for i in 0..previous.len() - 1 {
+            let spur_node = previous[i];
+            let root = &previous[..=i];
+
+            // Edges leaving the root that are already used by a path with the same root
+            let removed_edges: HashSet<(NodeId, NodeId)> = paths
+                .iter()
+                .filter(|p| p.len() > i + 1 && &p[..=i] == root)
+                .map(|p| (p[i], p[i + 1]))
+                .collect();
+            let removed_nodes: HashSet<NodeId> = root[..i].iter().copied().collect();
+
+            if let Some(spur) = constrained_shortest_path(
+                graph,
+                spur_node,
+                destination,
+                &removed_nodes,
+                &removed_edges,
+            ) {
+                let mut candidate = root[..i].to_vec();
+                candidate.extend(spur);
+                if !paths.contains(&candidate) && !candidates.contains(&candidate) {
+                    candidates.push(candidate);
+                }
+            }
+        }
+
+        if candidates.is_empty() {
+            break;
+        }
+        // Fewest hops first, then lowest node IDs, so that the result is deterministic
+        candidates.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+        paths.push(candidates.remove(0));
+    }
+    paths
+}
+
+/// Returns the loopless routes from `source` to `destination` with at most `max_hops` hops,
+/// by increasing number of hops. At most `MAX_SIMPLE_PATHS` routes are returned.
+pub fn simple_paths(
+    graph: &GraphState,
+    source: NodeId,
+    destination: NodeId,
+    max_hops: usize,
+) -> Vec<Vec<NodeId>> {
+    let mut paths = Vec::new();
+    let mut path = vec![source];
+    collect_simple_paths(graph, destination, max_hops, &mut path, &mut paths);
+    paths.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+    paths
+}
+
+fn collect_simple_paths(
+    graph: &GraphState,
+    destination: NodeId,
+    max_hops: usize,
+    path: &mut Vec<NodeId>,
+    paths: &mut Vec<Vec<NodeId>>,
+) {
+    if paths.len() >= MAX_SIMPLE_PATHS || path.len() > max_hops {
+        return;
+    }
+    let current = *path.last().unwrap();
+    for next in next_hops(graph, current, destination) {
+        if path.contains(&next) {
+            continue;
+        }
+        path.push(next);
+        if next == destination {
+            if paths.len() < MAX_SIMPLE_PATHS {
+                paths.push(path.clone());
+            }
+        } else {
+            collect_simple_paths(graph, destination, max_hops, path, paths);
+        }
+        path.pop();
+    }
+}
+
+/// Breadth-first search that avoids `removed_nodes` and the directed `removed_edges`.
+fn constrained_shortest_path(
+    graph: &GraphState,
+    source: NodeId,
+    destination: NodeId,
+    removed_nodes: &HashSet<NodeId>,
+    removed_edges: &HashSet<(NodeId, NodeId)>,
+) -> Option<Vec<NodeId>> {
+    if source == destination {
+        return None;
+    }
+
+    let mut previous = HashMap::new();
+    let mut visited: HashSet<NodeId> = removed_nodes.clone();
+    visited.insert(source);
+    let mut queue = VecDeque::from([source]);
+
+    while let Some(node) = queue.pop_front() {
+        for next in next_hops(graph, node, destination) {
+            if removed_edges.contains(&(node, next)) || !visited.insert(next) {
+                continue;
+            }
+            previous.insert(next, node);
+            if next == destination {
+                let mut path = vec![destination];
+                let mut current = destination;
+                while let Some(&prev) = previous.get(&current) {
+                    path.push(prev);
+                    current = prev;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            // Only drones forward packets
+            if matches!(graph.get_node_type(next), Some(NodeMetadata::Drone(_))) {
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::topology::test_graph;
+
+    /// Client 1 and server 2, linked by the drones 10, 11 and 12:
+    ///
+    /// ```text
+    /// 1 -- 10 -- 2
+    /// |  /       |
+    /// 11 ------- 12
+    /// ```
+    fn graph() -> GraphState {
+        test_graph(
+            &[(10, &[1, 11, 2]), (11, &[1, 10, 12]), (12, &[11, 2])],
+            &[(1, &[10, 11])],
+            &[(2, &[10, 12])],
+        )
+    }
+
+    #[test]
+    fn routes_only_end_at_hosts() {
+        let graph = graph();
+        assert!(check_host(&graph, 1).is_ok());
+        assert!(check_host(&graph, 2).is_ok());
+        assert!(matches!(
+            check_host(&graph, 10),
+            Err(NetworkError::InvalidOperation(_))
+        ));
+        assert!(matches!(
+            check_host(&graph, 99),
+            Err(NetworkError::NodeNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn hosts_do_not_forward() {
+        let graph = graph();
+        assert_eq!(next_hops(&graph, 10, 2), [2, 11]);
+        assert_eq!(next_hops(&graph, 10, 1), [1, 11]);
+    }
+
+    #[test]
+    fn shortest_path_has_the_fewest_hops() {
+        let graph = graph();
+        assert_eq!(shortest_path(&graph, 1, 2), Some(vec![1, 10, 2]));
+        assert_eq!(shortest_path(&graph, 2, 1), Some(vec![2, 10, 1]));
+        assert_eq!(shortest_path(&graph, 1, 1), None);
+    }
+
+    #[test]
+    fn yen_returns_routes_by_increasing_hops() {
+        let graph = graph();
+        assert_eq!(
+            k_shortest_paths(&graph, 1, 2, 3),
+            [vec![1, 10, 2], vec![1, 11, 10, 2], vec![1, 11, 12, 2]]
+        );
+        // Only four loopless routes exist
+        assert_eq!(
+            k_shortest_paths(&graph, 1, 2, 10),
+            [
+                vec![1, 10, 2],
+                vec![1, 11, 10, 2],
+                vec![1, 11, 12, 2],
+                vec![1, 10, 11, 12, 2],
+            ]
+        );
+    }
+
+    #[test]
+    fn no_route_is_requested() {
+        assert!(k_shortest_paths(&graph(), 1, 2, 0).is_empty());
+    }
+
+    #[test]
+    fn unreachable_destination_has_no_route() {
+        let mut graph = graph();
+        graph.remove_edge(10, 2).unwrap();
+        graph.remove_edge(12, 2).unwrap();
+        assert_eq!(shortest_path(&graph, 1, 2), None);
+        assert!(k_shortest_paths(&graph, 1, 2, 3).is_empty());
+    }
+
+    #[test]
+    fn simple_paths_are_bounded_by_hops() {
+        let graph = graph();
+        assert_eq!(simple_paths(&graph, 1, 2, 1), Vec::<Vec<NodeId>>::new());
+        assert_eq!(simple_paths(&graph, 1, 2, 2), [vec![1, 10, 2]]);
+        assert_eq!(
+            simple_paths(&graph, 1, 2, 4),
+            k_shortest_paths(&graph, 1, 2, 10)
+        );
+    }
+}
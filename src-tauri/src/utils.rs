@@ -1,4 +1,5 @@
 use common_utils::HostEvent;
+use std::time::{SystemTime, UNIX_EPOCH};
 use wg_2024::controller::DroneEvent;
 use wg_2024::network::NodeId;
 
@@ -7,3 +8,11 @@ pub enum ControllerEvent {
     Drone { node_id: NodeId, event: DroneEvent },
     Host { node_id: NodeId, event: HostEvent },
 }
+
+/// Returns the current UNIX time in milliseconds.
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
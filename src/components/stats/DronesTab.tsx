@@ -76,9 +76,9 @@ const DronesTab = ({ selectedDroneId }: { selectedDroneId: NetworkNode | null })
   };
 
 
-  // Convert timestamps to readable format and prepare chart data
+  // Timestamps are relative to the start of the run
   const lineChartData = metrics.time_series.map((point) => ({
-    time: new Date(point.timestamp * 1000).toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' }),
+    time: `+${ Math.floor(point.timestamp / 1000) }s`,
     sent: point.sent,
     dropped: point.dropped,
   }));
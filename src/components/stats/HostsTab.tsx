@@ -103,7 +103,7 @@ const HostsTab = ({ selectedHostId }: { selectedHostId: NetworkNode | null }) =>
   }));
 
   const lineChartData = metrics.time_series.map((point) => ({
-    time: new Date(point.timestamp * 1000).toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' }),
+    time: `+${ Math.floor(point.timestamp / 1000) }s`,
     sent: point.sent,
     acked: point.acked,
   }));
@@ -4,21 +4,83 @@ import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
 import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from "@/components/ui/select";
-import { ArrowDownToLine } from "lucide-react";
+import { save } from "@tauri-apps/plugin-dialog";
+import { ArrowDownToLine, Download } from "lucide-react";
 import { toast } from "sonner";
 import { useSimulation } from "@/components/SimulationContext.tsx";
 
+type PayloadView =
+  | { type: "MsgFragment"; fragment_index: number; total_n_fragments: number; length: number }
+  | { type: "Ack"; fragment_index: number }
+  | { type: "Nack"; fragment_index: number; nack_type: string; error_node: number | null }
+  | { type: "FloodRequest"; flood_id: number; initiator_id: number; path_trace: [number, string][] }
+  | { type: "FloodResponse"; flood_id: number; path_trace: [number, string][] }
+  | { type: "HeaderOnly"; packet_type: string };
+
+interface PacketView {
+  source: number | null;
+  destination: number | null;
+  hops: number[];
+  hop_index: number;
+  session_id: number | null;
+  payload: PayloadView;
+}
+
+interface HostMessageView {
+  destination: number;
+  latency_ms: number;
+  kind: string | null;
+  content: unknown;
+}
+
 interface Message {
-  id: number;
-  event_type: "PacketSent" | "PacketDropped" | "ControllerShortcut" | "HostMessageSent";
+  seq: number;
+  run_id: number;
+  timestamp: number;
+  sim_time_ms: number;
+  event_type: "PacketSent" | "PacketDropped" | "ControllerShortcut" | "HostMessageSent" | "ShortcutFailed" | "PacketDelivered";
   node: number;
-  node_type: "Host" | "Drone"; // Node Type (New Field)
-  packet: string;
+  node_type: "Host" | "Drone";
+  packet: PacketView | null;
+  host_message: HostMessageView | null;
+  shortcut_failure: string | null;
 }
 
+// Render the typed payload of a packet as a single line
+const describePayload = (payload: PayloadView): string => {
+  switch (payload.type) {
+    case "MsgFragment":
+      return `MsgFragment ${ payload.fragment_index + 1 }/${ payload.total_n_fragments } (${ payload.length } bytes)`;
+    case "Ack":
+      return `Ack ${ payload.fragment_index }`;
+    case "Nack":
+      return `Nack ${ payload.fragment_index } ${ payload.nack_type }${ payload.error_node !== null ? ` (node ${ payload.error_node })` : "" }`;
+    case "FloodRequest":
+      return `FloodRequest #${ payload.flood_id } from ${ payload.initiator_id } trace [${ payload.path_trace.map(([id]) => id).join(", ") }]`;
+    case "FloodResponse":
+      return `FloodResponse #${ payload.flood_id } trace [${ payload.path_trace.map(([id]) => id).join(", ") }]`;
+    case "HeaderOnly":
+      return payload.packet_type;
+  }
+};
+
+const describeMessage = (message: Message): string => {
+  if (message.host_message) {
+    const { destination, latency_ms, kind, content } = message.host_message;
+    return `${ kind ?? "message" } to ${ destination } in ${ latency_ms }ms: ${ JSON.stringify(content) }`;
+  }
+  if (message.packet) {
+    const { hops, hop_index, session_id, payload } = message.packet;
+    const session = session_id !== null ? `session ${ session_id } ` : "";
+    const failure = message.shortcut_failure ? ` (${ message.shortcut_failure })` : "";
+    return `${ describePayload(payload) } ${ session }route [${ hops.join(" → ") }] hop ${ hop_index }${ failure }`;
+  }
+  return "";
+};
+
 export const Logs = () => {
   const [messages, setMessages] = useState<Message[]>([]);
-  const [lastMessageId, setLastMessageId] = useState<number>(0);
+  const [lastSeq, setLastSeq] = useState<number>(0);
   const [nodeFilter, setNodeFilter] = useState<string>("");
   const [eventTypeFilter, setEventTypeFilter] = useState<string>("all"); // Default: All event types
   const [nodeTypeFilter, setNodeTypeFilter] = useState<string>("all"); // Default: All nodes (Host/Drone)
@@ -33,7 +95,7 @@ export const Logs = () => {
   const fetchAndDisplayMessages = async () => {
     try {
       const response = await invoke<Message[]>("get_new_messages", {
-        lastId: lastMessageId,
+        lastSeq: lastSeq,
         maxMessages: maxMessages,
       });
 
@@ -52,8 +114,8 @@ export const Logs = () => {
             : newMessages;
         });
 
-        // Update last received message ID
-        setLastMessageId(response[response.length - 1].id);
+        // Update last received sequence number
+        setLastSeq(response[response.length - 1].seq);
       }
     } catch (error) {
       toast.error("Failed to fetch messages. Please check the backend.");
@@ -65,7 +127,7 @@ export const Logs = () => {
     fetchAndDisplayMessages().then(r => r);
     const interval = setInterval(fetchAndDisplayMessages, pollingInterval);
     return () => clearInterval(interval);
-  }, [lastMessageId]);
+  }, [lastSeq]);
 
   useEffect(() => {
     const handleStorageChange = () => {
@@ -104,6 +166,10 @@ export const Logs = () => {
         return "text-red-500";
       case "ControllerShortcut":
         return "text-blue-500";
+      case "ShortcutFailed":
+        return "text-orange-500";
+      case "PacketDelivered":
+        return "text-purple-500";
       default:
         return "text-gray-700";
     }
@@ -140,11 +206,26 @@ export const Logs = () => {
     return result;
   }, [nodeFilter, eventTypeFilter, nodeTypeFilter, messages]);
 
+  // Export the packets captured by the sniffer during the last run, for Wireshark
+  const exportCapture = async () => {
+    try {
+      const path = await save({ filters: [{ name: "pcapng", extensions: ["pcapng"] }] });
+      if (!path) return;
+      const count = await invoke<number>("export_pcapng", { path });
+      toast.success(`Exported ${ count } packets`);
+    } catch (error) {
+      toast.error(`Failed to export the capture: ${ error }`);
+    }
+  };
+
   return (
     <div className="h-full overflow-hidden w-full flex flex-col p-4 pt-1 bg-background">
       {/* Header and Filters */ }
       <div className="flex items-center justify-between pb-2 border-b gap-4">
         <h2 className="text-2xl font-semibold">Logs</h2>
+        <Button size="sm" variant="outline" onClick={ exportCapture } title="Export the sniffer capture (pcapng)">
+          <Download className="w-4 h-4"/>
+        </Button>
 
         {/* Filters */ }
         <div
@@ -175,6 +256,8 @@ export const Logs = () => {
                 <SelectItem value="PacketDropped">Packet Dropped</SelectItem>
                 <SelectItem value="ControllerShortcut">Controller Shortcut</SelectItem>
                 <SelectItem value="HostMessageSent">Host Message Sent</SelectItem>
+                <SelectItem value="ShortcutFailed">Shortcut Failed</SelectItem>
+                <SelectItem value="PacketDelivered">Packet Delivered</SelectItem>
               </SelectContent>
             </Select>
           </div>
@@ -205,11 +288,12 @@ export const Logs = () => {
           <p className="text-muted-foreground text-center">No messages received...</p>
         ) : (
           filteredMessages.map((message, index) => (
-            <div key={ `${ message.id }-${ index }` }
+            <div key={ `${ message.seq }-${ index }` }
                  className={ `py-1 text-xs ${ getMessageColor(message.event_type) }` }>
-              <span className="text-blue-600">[{ message.node_type }: { message.node }]</span>
+              <span className="text-muted-foreground">[run { message.run_id } +{ (message.sim_time_ms / 1000).toFixed(3) }s]</span>
+              <span className="ml-2 text-blue-600">[{ message.node_type }: { message.node }]</span>
               <span className="ml-2 font-semibold">[{ message.event_type }]</span>
-              <span className="ml-2 text-foreground">{ message.packet }</span>
+              <span className="ml-2 text-foreground">{ describeMessage(message) }</span>
             </div>
           ))
         ) }
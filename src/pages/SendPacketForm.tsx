@@ -1,3 +1,4 @@
+import { useState } from "react";
 import { useForm } from "react-hook-form";
 import { z } from "zod";
 import { zodResolver } from "@hookform/resolvers/zod";
@@ -27,21 +28,36 @@ const nackTypeEnum = [
   "ErrorInRouting",
   "UnexpectedRecipient",
 ] as const;
+const nodeTypeEnum = ["Client", "Drone", "Server"] as const;
+const dataEncodingEnum = ["Text", "Hex"] as const;
+
+// Maximum size of the data of a fragment (wg_2024 FRAGMENT_DSIZE)
+const FRAGMENT_DSIZE = 128;
 
 const packetSchema = z.object({
-  senderId: z.coerce.number().min(1, "Sender ID is required"),
-  sessionId: z.coerce.number().min(1, "Session ID is required"),
+  sessionId: z.coerce.number().min(0, "Session ID is required"),
   packetType: z.enum(packetTypeEnum),
   hops: z.string().refine(
     (value) =>
       value.split(",").every((id) => !isNaN(Number(id.trim()))),
     { message: "Enter a comma‐separated list of nodes (e.g., 1,2,3)" }
   ),
+  hopIndex: z.coerce.number().min(0),
+  injectInto: z.string().optional(),
   fragmentIndex: z.coerce.number().optional(),
   totalFragments: z.coerce.number().optional(),
+  dataEncoding: z.enum(dataEncodingEnum),
   data: z.string().optional(),
+  length: z.string().optional(),
   nackType: z.string().optional(),
   errorNode: z.coerce.number().optional(),
+  floodId: z.coerce.number().optional(),
+  initiatorId: z.coerce.number().optional(),
+  initiatorType: z.enum(nodeTypeEnum),
+  pathTrace: z.string().refine(
+    (value) => value.trim() === "" || value.split(",").every((entry) => /^\s*\d+\s*:\s*(Client|Drone|Server)\s*$/.test(entry)),
+    { message: "Enter a comma‐separated list of id:type (e.g., 1:Client,2:Drone)" }
+  ).optional(),
 });
 
 // ─── TYPES ──────────────────────────────────────────────────────────────────────
@@ -49,18 +65,93 @@ const packetSchema = z.object({
 type PacketData = z.infer<typeof packetSchema>;
 type PacketType = typeof packetTypeEnum[number];
 
+interface SuggestedRoute {
+  hops: number[];
+  length: number;
+}
+
 // ─── DEFAULT VALUES ────────────────────────────────────────────────────────────
 
 const DEFAULT_VALUES: PacketData = {
-  senderId: 1,
   sessionId: 1,
   packetType: packetTypeEnum[0],
   hops: "",
+  hopIndex: 1,
+  injectInto: "",
   fragmentIndex: 0,
   totalFragments: 1,
+  dataEncoding: "Text",
   data: "",
+  length: "",
   nackType: nackTypeEnum[0],
   errorNode: undefined,
+  floodId: 0,
+  initiatorId: undefined,
+  initiatorType: "Client",
+  pathTrace: "",
+};
+
+// ─── PACKET SPEC ───────────────────────────────────────────────────────────────
+
+const encodeData = (data: string, encoding: typeof dataEncodingEnum[number]): number[] => {
+  if (encoding === "Hex") {
+    const hex = data.replace(/\s+/g, "");
+    if (hex.length % 2 !== 0 || /[^0-9a-fA-F]/.test(hex)) {
+      throw new Error("Invalid hex data");
+    }
+    return hex.match(/.{2}/g)?.map((byte) => parseInt(byte, 16)) ?? [];
+  }
+  return Array.from(new TextEncoder().encode(data));
+};
+
+const parsePathTrace = (value: string): [number, string][] =>
+  value
+    .split(",")
+    .filter((entry) => entry.trim() !== "")
+    .map((entry) => {
+      const [id, type] = entry.split(":").map((part) => part.trim());
+      return [Number(id), type];
+    });
+
+// Builds the payload expected by the backend PacketSpec
+const buildPayload = (data: PacketData) => {
+  switch (data.packetType) {
+    case "MsgFragment": {
+      const bytes = encodeData(data.data ?? "", data.dataEncoding);
+      if (bytes.length > FRAGMENT_DSIZE) {
+        throw new Error(`Fragment data is ${ bytes.length } bytes, the maximum is ${ FRAGMENT_DSIZE }`);
+      }
+      return {
+        type: "MsgFragment",
+        fragment_index: data.fragmentIndex ?? 0,
+        total_n_fragments: data.totalFragments ?? 1,
+        data: bytes,
+        length: data.length ? Number(data.length) : null,
+      };
+    }
+    case "Ack":
+      return { type: "Ack", fragment_index: data.fragmentIndex ?? 0 };
+    case "Nack": {
+      const nackType = ["ErrorInRouting", "UnexpectedRecipient"].includes(data.nackType ?? "")
+        ? { [data.nackType as string]: Number(data.errorNode) }
+        : data.nackType;
+      return { type: "Nack", fragment_index: data.fragmentIndex ?? 0, nack_type: nackType };
+    }
+    case "FloodRequest":
+      return {
+        type: "FloodRequest",
+        flood_id: data.floodId ?? 0,
+        initiator_id: data.initiatorId ?? 0,
+        initiator_type: data.initiatorType,
+        path_trace: data.pathTrace?.trim() ? parsePathTrace(data.pathTrace) : null,
+      };
+    case "FloodResponse":
+      return {
+        type: "FloodResponse",
+        flood_id: data.floodId ?? 0,
+        path_trace: parsePathTrace(data.pathTrace ?? ""),
+      };
+  }
 };
 
 // ─── COMPONENT ────────────────────────────────────────────────────────────────
@@ -74,6 +165,33 @@ export default function SendPacketForm() {
     defaultValues: DEFAULT_VALUES,
   });
 
+  // Routes suggested between the first and the last node of the path
+  const [suggestedRoutes, setSuggestedRoutes] = useState<SuggestedRoute[]>([]);
+
+  const suggestRoutes = async () => {
+    const nodes = form.getValues("hops")
+      .split(",")
+      .map((id) => id.trim())
+      .filter((id) => id !== "")
+      .map(Number);
+    if (nodes.length < 2) {
+      toast.error("Enter at least the source and the destination of the path");
+      return;
+    }
+    try {
+      const routes = await invoke<SuggestedRoute[]>("suggest_routes", {
+        source: nodes[0],
+        destination: nodes[nodes.length - 1],
+      });
+      if (routes.length === 0) {
+        toast.error("No route found");
+      }
+      setSuggestedRoutes(routes);
+    } catch (error) {
+      toast.error(`Error suggesting routes: ${ error }`);
+    }
+  };
+
   // ─── SEND PACKET ─────────────────────────────────────────────────────────────
   const sendPacket = async (packet: any) => {
     await invoke("send_packet", packet);
@@ -82,25 +200,13 @@ export default function SendPacketForm() {
   // For a single packet we convert the hops from a comma-separated string to an array.
   const handleSinglePacket = async (data: PacketData, hopsArray: number[]) => {
     await sendPacket({
-      senderId: Number(data.senderId),
-      sessionId: Number(data.sessionId),
-      packetType: data.packetType,
-      hops: hopsArray,
-      fragmentIndex: ["MsgFragment", "Ack", "Nack"].includes(data.packetType)
-        ? data.fragmentIndex ?? 0
-        : 0,
-      totalFragments:
-        data.packetType === "MsgFragment" ? (data.totalFragments ?? 1) : 0,
-      data: ["MsgFragment", "FloodRequest", "FloodResponse"].includes(
-        data.packetType
-      )
-        ? data.data ?? ""
-        : "",
-      nackType: data.packetType === "Nack" ? data.nackType : undefined,
-      errorNode:
-        data.packetType === "Nack" && data.errorNode
-          ? Number(data.errorNode)
-          : undefined,
+      packet: {
+        session_id: Number(data.sessionId),
+        hops: hopsArray,
+        hop_index: Number(data.hopIndex),
+        payload: buildPayload(data),
+        inject_into: data.injectInto?.trim() ? Number(data.injectInto) : null,
+      },
     });
     toast.success("Packet sent successfully!");
   };
@@ -129,19 +235,6 @@ export default function SendPacketForm() {
         <form onSubmit={ form.handleSubmit(onSubmit) } className="space-y-6">
           {/* Main Fields */ }
           <div className="grid grid-cols-3 gap-4">
-            <FormField
-              control={ form.control }
-              name="senderId"
-              render={ ({ field }) => (
-                <FormItem>
-                  <FormLabel>Sender ID</FormLabel>
-                  <FormControl>
-                    <Input type="number" { ...field } />
-                  </FormControl>
-                  <FormMessage/>
-                </FormItem>
-              ) }
-            />
             <FormField
               control={ form.control }
               name="sessionId"
@@ -189,17 +282,68 @@ export default function SendPacketForm() {
             render={ ({ field }) => (
               <FormItem>
                 <FormLabel>Path (Hops)</FormLabel>
-                <FormControl>
-                  <Input
-                    type="text"
-                    { ...field }
-                    placeholder="Example: 1,2,3,4"
-                  />
-                </FormControl>
+                <div className="flex flex-row gap-2">
+                  <FormControl>
+                    <Input
+                      type="text"
+                      { ...field }
+                      placeholder="Example: 1,2,3,4"
+                    />
+                  </FormControl>
+                  <Button type="button" variant="outline" onClick={ suggestRoutes }>
+                    Suggest
+                  </Button>
+                </div>
                 <FormMessage/>
               </FormItem>
             ) }
           />
+          { suggestedRoutes.length > 0 && (
+            <div className="flex flex-wrap gap-2">
+              { suggestedRoutes.map((route) => (
+                <Button
+                  key={ route.hops.join(",") }
+                  type="button"
+                  size="sm"
+                  variant="secondary"
+                  onClick={ () => {
+                    form.setValue("hops", route.hops.join(","));
+                    setSuggestedRoutes([]);
+                  } }
+                >
+                  { route.hops.join(" → ") } ({ route.length } hops)
+                </Button>
+              )) }
+            </div>
+          ) }
+          <div className="grid grid-cols-2 gap-4">
+            <FormField
+              control={ form.control }
+              name="hopIndex"
+              render={ ({ field }) => (
+                <FormItem>
+                  <FormLabel>Hop Index</FormLabel>
+                  <FormControl>
+                    <Input type="number" { ...field } />
+                  </FormControl>
+                  <FormMessage/>
+                </FormItem>
+              ) }
+            />
+            <FormField
+              control={ form.control }
+              name="injectInto"
+              render={ ({ field }) => (
+                <FormItem>
+                  <FormLabel>Inject Into</FormLabel>
+                  <FormControl>
+                    <Input type="number" { ...field } placeholder="Default: hop at Hop Index"/>
+                  </FormControl>
+                  <FormMessage/>
+                </FormItem>
+              ) }
+            />
+          </div>
           {/* For MsgFragment, Ack, and Nack: show a fragment index;
               if MsgFragment, also show total fragments */ }
           { ["MsgFragment", "Ack", "Nack"].includes(form.watch("packetType")) && (
@@ -234,27 +378,136 @@ export default function SendPacketForm() {
               ) }
             </div>
           ) }
-          {/* For packets that include content */ }
-          { ["MsgFragment", "FloodRequest", "FloodResponse"].includes(
-            form.watch("packetType")
-          ) && (
-            <FormField
-              control={ form.control }
-              name="data"
-              render={ ({ field }) => (
-                <FormItem>
-                  <FormLabel>Data</FormLabel>
-                  <FormControl>
-                    <Input
-                      type="text"
-                      { ...field }
-                      placeholder="Packet content"
-                    />
-                  </FormControl>
-                  <FormMessage/>
-                </FormItem>
+          {/* For fragments: the raw content, as text or hex bytes */ }
+          { form.watch("packetType") === "MsgFragment" && (
+            <div className="grid grid-cols-4 gap-4">
+              <FormField
+                control={ form.control }
+                name="dataEncoding"
+                render={ ({ field }) => (
+                  <FormItem>
+                    <FormLabel>Encoding</FormLabel>
+                    <Select onValueChange={ field.onChange } defaultValue={ field.value }>
+                      <FormControl>
+                        <SelectTrigger>
+                          <SelectValue/>
+                        </SelectTrigger>
+                      </FormControl>
+                      <SelectContent>
+                        { dataEncodingEnum.map((encoding) => (
+                          <SelectItem key={ encoding } value={ encoding }>
+                            { encoding }
+                          </SelectItem>
+                        )) }
+                      </SelectContent>
+                    </Select>
+                    <FormMessage/>
+                  </FormItem>
+                ) }
+              />
+              <FormField
+                control={ form.control }
+                name="data"
+                render={ ({ field }) => (
+                  <FormItem className="col-span-2">
+                    <FormLabel>Data</FormLabel>
+                    <FormControl>
+                      <Input
+                        type="text"
+                        { ...field }
+                        placeholder={ form.watch("dataEncoding") === "Hex" ? "Example: 48 65 6c 6c 6f" : "Packet content" }
+                      />
+                    </FormControl>
+                    <FormMessage/>
+                  </FormItem>
+                ) }
+              />
+              <FormField
+                control={ form.control }
+                name="length"
+                render={ ({ field }) => (
+                  <FormItem>
+                    <FormLabel>Length</FormLabel>
+                    <FormControl>
+                      <Input type="number" { ...field } placeholder="Data length"/>
+                    </FormControl>
+                    <FormMessage/>
+                  </FormItem>
+                ) }
+              />
+            </div>
+          ) }
+          {/* For flood packets: the flood id, the initiator and the path trace */ }
+          { ["FloodRequest", "FloodResponse"].includes(form.watch("packetType")) && (
+            <div className="grid grid-cols-3 gap-4">
+              <FormField
+                control={ form.control }
+                name="floodId"
+                render={ ({ field }) => (
+                  <FormItem>
+                    <FormLabel>Flood ID</FormLabel>
+                    <FormControl>
+                      <Input type="number" { ...field } />
+                    </FormControl>
+                    <FormMessage/>
+                  </FormItem>
+                ) }
+              />
+              { form.watch("packetType") === "FloodRequest" && (
+                <>
+                  <FormField
+                    control={ form.control }
+                    name="initiatorId"
+                    render={ ({ field }) => (
+                      <FormItem>
+                        <FormLabel>Initiator ID</FormLabel>
+                        <FormControl>
+                          <Input type="number" { ...field } />
+                        </FormControl>
+                        <FormMessage/>
+                      </FormItem>
+                    ) }
+                  />
+                  <FormField
+                    control={ form.control }
+                    name="initiatorType"
+                    render={ ({ field }) => (
+                      <FormItem>
+                        <FormLabel>Initiator Type</FormLabel>
+                        <Select onValueChange={ field.onChange } defaultValue={ field.value }>
+                          <FormControl>
+                            <SelectTrigger>
+                              <SelectValue/>
+                            </SelectTrigger>
+                          </FormControl>
+                          <SelectContent>
+                            { nodeTypeEnum.map((type) => (
+                              <SelectItem key={ type } value={ type }>
+                                { type }
+                              </SelectItem>
+                            )) }
+                          </SelectContent>
+                        </Select>
+                        <FormMessage/>
+                      </FormItem>
+                    ) }
+                  />
+                </>
               ) }
-            />
+              <FormField
+                control={ form.control }
+                name="pathTrace"
+                render={ ({ field }) => (
+                  <FormItem className="col-span-3">
+                    <FormLabel>Path Trace</FormLabel>
+                    <FormControl>
+                      <Input type="text" { ...field } placeholder="Example: 1:Client,2:Drone"/>
+                    </FormControl>
+                    <FormMessage/>
+                  </FormItem>
+                ) }
+              />
+            </div>
           ) }
           {/* For Nack: show the nack type and, when needed, an error node id */ }
           { form.watch("packetType") === "Nack" && (
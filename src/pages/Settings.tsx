@@ -38,6 +38,8 @@ const Settings = () => {
   const [discoveryIntervalSent, setDiscoveryIntervalSent] = useState<boolean>(true);
 
   const [strict, setStrict] = useState<boolean>(false);
+  // Sniffer on the inbound channels of the nodes
+  const [sniffer, setSniffer] = useState<boolean>(false);
 
   const { richColors, setRichColors } = useTheme();
 
@@ -175,6 +177,14 @@ const Settings = () => {
         console.error("Failed to fetch strict mode:", error);
         toast.error("Error fetching strict mode.");
       }
+
+      try {
+        const snifferEnabled = await invoke<boolean>("get_sniffer_enabled");
+        setSniffer(snifferEnabled);
+      } catch (error) {
+        console.error("Failed to fetch sniffer setting:", error);
+        toast.error("Error fetching sniffer setting.");
+      }
     };
 
     fetchSettings().then(r => r);
@@ -192,6 +202,17 @@ const Settings = () => {
     }
   };
 
+  const toggleSniffer = async () => {
+    try {
+      await invoke("set_sniffer_enabled", { enabled: !sniffer });
+      setSniffer(!sniffer);
+      toast.success(`Packet Sniffer ${ !sniffer ? "enabled" : "disabled" } from the next run!`);
+    } catch (error) {
+      console.error("Error updating Packet Sniffer:", error);
+      toast.error("Failed to update Packet Sniffer.");
+    }
+  };
+
 
   return (
     <div className="p-6 pb-20 max-w-3xl mx-auto overflow-y-scroll scrollbar-hide">
@@ -319,6 +340,11 @@ const Settings = () => {
           <Switch id="strictMode" checked={ strict } onCheckedChange={ toggleStrictMode }/>
         </div>
 
+        <div className="flex flex-col items-center justify-around">
+          <Label htmlFor="sniffer">Packet Sniffer</Label>
+          <Switch id="sniffer" checked={ sniffer } onCheckedChange={ toggleSniffer } disabled={ status === "Running" }/>
+        </div>
+
         {/* Client URL */ }
         <div className="space-y-2">
           <Label htmlFor="clientUrl">Client URL</Label>
@@ -0,0 +1,144 @@
+-- Wireshark dissector for the wg_2024 packets exported by the simulation controller.
+--
+-- The captures are written by `export_pcapng` with the link type LINKTYPE_USER0 (147),
+-- one interface per receiving node (named `node-<id>`). Each frame is the binary encoding
+-- of a `wg_2024::packet::Packet` described in `simulation/sniffer/pcapng.rs`; every
+-- integer is big-endian.
+--
+-- Usage: copy this file to the Wireshark personal plugins folder
+-- (Help > About Wireshark > Folders), or run
+--     wireshark -X lua_script:tools/wireshark/wg_2024_dissector.lua capture.pcapng
+--
+-- Example display filters:
+--     wg2024.type == 2 && wg2024.nack.type == 2      dropped fragments
+--     wg2024.session == 42                           a single session
+--     wg2024.hop == 3                                packets routed through node 3
+
+local wg2024 = Proto("wg2024", "wg_2024 Drone Network")
+
+local packet_types = {
+    [0] = "MsgFragment",
+    [1] = "Ack",
+    [2] = "Nack",
+    [3] = "FloodRequest",
+    [4] = "FloodResponse",
+}
+local nack_types = {
+    [0] = "ErrorInRouting",
+    [1] = "DestinationIsDrone",
+    [2] = "Dropped",
+    [3] = "UnexpectedRecipient",
+}
+local node_types = {
+    [0] = "Client",
+    [1] = "Drone",
+    [2] = "Server",
+}
+
+local f = {
+    version = ProtoField.uint8("wg2024.version", "Encoding version"),
+    type = ProtoField.uint8("wg2024.type", "Packet type", base.DEC, packet_types),
+    session = ProtoField.uint64("wg2024.session", "Session ID"),
+    hop_index = ProtoField.uint8("wg2024.hop_index", "Hop index"),
+    hop_count = ProtoField.uint8("wg2024.hop_count", "Number of hops"),
+    hop = ProtoField.uint8("wg2024.hop", "Hop"),
+    source = ProtoField.uint8("wg2024.source", "Source"),
+    destination = ProtoField.uint8("wg2024.destination", "Destination"),
+    fragment_index = ProtoField.uint64("wg2024.fragment.index", "Fragment index"),
+    total_fragments = ProtoField.uint64("wg2024.fragment.total", "Total fragments"),
+    length = ProtoField.uint8("wg2024.fragment.length", "Length"),
+    data = ProtoField.bytes("wg2024.fragment.data", "Data"),
+    nack_type = ProtoField.uint8("wg2024.nack.type", "NACK type", base.DEC, nack_types),
+    nack_node = ProtoField.uint8("wg2024.nack.node", "NACK node"),
+    flood_id = ProtoField.uint64("wg2024.flood.id", "Flood ID"),
+    initiator = ProtoField.uint8("wg2024.flood.initiator", "Initiator"),
+    path_length = ProtoField.uint8("wg2024.flood.path_length", "Path length"),
+    path_node = ProtoField.uint8("wg2024.flood.node", "Node"),
+    path_node_type = ProtoField.uint8("wg2024.flood.node_type", "Node type", base.DEC, node_types),
+}
+wg2024.fields = f
+
+local function dissect_path(buffer, offset, tree)
+    local length = buffer(offset, 1):uint()
+    local path = tree:add(f.path_length, buffer(offset, 1))
+    offset = offset + 1
+    for _ = 1, length do
+        local entry = path:add(wg2024, buffer(offset, 2), string.format(
+            "%d (%s)", buffer(offset, 1):uint(), node_types[buffer(offset + 1, 1):uint()] or "?"))
+        entry:add(f.path_node, buffer(offset, 1))
+        entry:add(f.path_node_type, buffer(offset + 1, 1))
+        offset = offset + 2
+    end
+    return offset
+end
+
+function wg2024.dissector(buffer, pinfo, tree)
+    if buffer:len() < 12 then
+        return 0
+    end
+    pinfo.cols.protocol = "WG2024"
+
+    local subtree = tree:add(wg2024, buffer(), "wg_2024 Packet")
+    subtree:add(f.version, buffer(0, 1))
+    local packet_type = buffer(1, 1):uint()
+    subtree:add(f.type, buffer(1, 1))
+    subtree:add(f.session, buffer(2, 8))
+
+    -- Routing header
+    local hop_index = buffer(10, 1):uint()
+    local hop_count = buffer(11, 1):uint()
+    local header = subtree:add(wg2024, buffer(10, 2 + hop_count), "Source routing header")
+    header:add(f.hop_index, buffer(10, 1))
+    header:add(f.hop_count, buffer(11, 1))
+    local hops = {}
+    for i = 0, hop_count - 1 do
+        header:add(f.hop, buffer(12 + i, 1))
+        hops[#hops + 1] = tostring(buffer(12 + i, 1):uint())
+    end
+    if hop_count > 0 then
+        header:add(f.source, buffer(12, 1)):set_generated()
+        header:add(f.destination, buffer(12 + hop_count - 1, 1)):set_generated()
+    end
+    local offset = 12 + hop_count
+
+    local info = string.format("%s session=%s route=[%s] hop=%d",
+        packet_types[packet_type] or "Unknown", buffer(2, 8):uint64(), table.concat(hops, ","), hop_index)
+
+    -- Payload
+    local payload = subtree:add(wg2024, buffer(offset), packet_types[packet_type] or "Unknown payload")
+    if packet_type == 0 then
+        payload:add(f.fragment_index, buffer(offset, 8))
+        payload:add(f.total_fragments, buffer(offset + 8, 8))
+        local length = buffer(offset + 16, 1):uint()
+        payload:add(f.length, buffer(offset + 16, 1))
+        payload:add(f.data, buffer(offset + 17, length))
+        info = info .. string.format(" fragment %s/%s",
+            buffer(offset, 8):uint64(), buffer(offset + 8, 8):uint64())
+    elseif packet_type == 1 then
+        payload:add(f.fragment_index, buffer(offset, 8))
+        info = info .. string.format(" fragment %s", buffer(offset, 8):uint64())
+    elseif packet_type == 2 then
+        payload:add(f.fragment_index, buffer(offset, 8))
+        payload:add(f.nack_type, buffer(offset + 8, 1))
+        local nack_type = buffer(offset + 8, 1):uint()
+        if nack_type == 0 or nack_type == 3 then
+            payload:add(f.nack_node, buffer(offset + 9, 1))
+        end
+        info = info .. string.format(" fragment %s %s",
+            buffer(offset, 8):uint64(), nack_types[nack_type] or "?")
+    elseif packet_type == 3 then
+        payload:add(f.flood_id, buffer(offset, 8))
+        payload:add(f.initiator, buffer(offset + 8, 1))
+        dissect_path(buffer, offset + 9, payload)
+        info = info .. string.format(" flood %s from %d", buffer(offset, 8):uint64(), buffer(offset + 8, 1):uint())
+    elseif packet_type == 4 then
+        payload:add(f.flood_id, buffer(offset, 8))
+        dissect_path(buffer, offset + 8, payload)
+        info = info .. string.format(" flood %s", buffer(offset, 8):uint64())
+    end
+
+    pinfo.cols.info = info
+    return buffer:len()
+end
+
+DissectorTable.get("wtap_encap"):add(wtap.USER0, wg2024)